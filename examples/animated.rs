@@ -6,7 +6,7 @@ use mandelbrot::mandelbrot::{Mandelbrot, MandelbrotConfig, Viewport};
 use png::HasParameters;
 
 mod shared;
-use shared::{cli, common};
+use shared::cli;
 use std::io::BufWriter;
 
 use mandelbrot::complex_number;
@@ -28,34 +28,34 @@ fn main() {
     encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
     let mut writer = encoder.write_header().unwrap();
 
-    let viewport = Viewport::<f64> {
-        top_left: complex_number::ComplexNumber::new(-3.0, 1.15),
-        width: 4f64,
-        height: (h as f64 / w as f64) * 4f64,
-    };
+    // The whole set, fit to the image:
+    let viewport = Viewport::from_center_zoom(
+        complex_number::ComplexNumber::new(-1.0, 0.0),
+        1.0,
+        dimensions,
+    );
 
-    let config = MandelbrotConfig::<u8> {
+    let config = MandelbrotConfig::<u8>::new(
         dimensions,
         viewport,
-        color_fn: ContinuousColorScale::get_color_fn_boxed(140.0, 1.0, 1.0),
-        // color_fn: ContinuousColorScale::pixel_color,
-    };
+        Box::new(ContinuousColorScale::new(140.0, 1.0, 1.0, 10.0).expect("Invalid colors")),
+        // Box::new(ContinuousColorScale::default()),
+    );
 
     println!("Running {} iterations", f);
 
-    let mut mandelbrot = Mandelbrot::new(config);
+    let mut mandelbrot = Mandelbrot::new(config).expect("Invalid dimensions or viewport");
     mandelbrot.run_iterations(f);
 
     writer
-        .write_frame(common::flatten_array(mandelbrot.get_pixels()).as_slice())
+        .write_frame(mandelbrot.get_pixels().as_rgba_bytes())
         .unwrap();
 
     mandelbrot.reset();
 
     for _i in 0..f {
         mandelbrot.run_iterations(1);
-        let data = common::flatten_array(mandelbrot.get_pixels());
-        let data = data.as_slice();
+        let data = mandelbrot.get_pixels().as_rgba_bytes();
 
         writer.write_frame(data).unwrap();
     }
@@ -11,7 +11,7 @@ use mandelbrot::{
 };
 
 mod shared;
-use shared::{cli, common};
+use shared::cli;
 
 fn main() {
     let matches = cli::args().get_matches();
@@ -25,21 +25,37 @@ fn main() {
 
     encoder.set(gif::Repeat::Infinite).unwrap();
 
-    let viewport = Viewport::<f64> {
-        top_left: complex_number::ComplexNumber::new(-3.0, 1.15),
-        width: 4f64,
-        height: (h as f64 / w as f64) * 4f64,
-    };
+    // The whole set, fit to the image:
+    let viewport = Viewport::from_center_zoom(
+        complex_number::ComplexNumber::new(-1.0, 0.0),
+        1.0,
+        dimensions,
+    );
 
-    let config = MandelbrotConfig::<u8> {
+    let config = MandelbrotConfig::<u8>::new(
         dimensions,
         viewport,
-        color_fn: ContinuousColorScale::get_color_fn_boxed(140.0, 1.0, 1.0),
-        /* color_fn: ContinuousColorScale::pixel_color,
-         * color_fn: SimpleColorScale::pixel_color, */
-    };
-
-    let mut mandelbrot = Mandelbrot::new(config);
+        Box::new(ContinuousColorScale::new(140.0, 1.0, 1.0, 10.0).expect("Invalid colors")),
+        /* Box::new(ContinuousColorScale::default()),
+         * Box::new(SimpleColorScale {}), */
+    );
+
+    let mut mandelbrot = Mandelbrot::new(config).expect("Invalid dimensions or viewport");
+
+    // With a palette, the frames cycle its colors around instead (which only
+    // takes one pass of iterations):
+    if let Some(palette) = cli::get_palette(&matches) {
+        let gradient = palette.unwrap_or_else(|e| panic!("{}", e));
+        mandelbrot.run_iterations(50);
+
+        for pixels in mandelbrot.cycle_colors(&gradient, f) {
+            let frame =
+                gif::Frame::from_rgba(w as u16, h as u16, &mut pixels.as_rgba_bytes().to_vec());
+            encoder.write_frame(&frame).unwrap();
+        }
+
+        return;
+    }
 
     println!("Running {} iterations", f);
 
@@ -50,7 +66,7 @@ fn main() {
         let pixels = mandelbrot.get_pixels();
         println!("Got the pixels");
 
-        let frame = gif::Frame::from_rgba(w as u16, h as u16, &mut common::flatten_array(pixels));
+        let frame = gif::Frame::from_rgba(w as u16, h as u16, &mut pixels.as_rgba_bytes().to_vec());
 
         println!("Made a frame");
 
@@ -1,6 +1,10 @@
 extern crate clap;
 use clap::{App, AppSettings, Arg, ArgMatches};
 
+use mandelbrot::{
+    expression::Expression, gradient::Gradient, mandelbrot::Viewport, palette,
+    sampling::Supersampling,
+};
 use std::{fs::File, io::Result, path::Path};
 
 macro_rules! env_or {
@@ -52,6 +56,42 @@ pub fn args() -> App<'static, 'static> {
                 .number_of_values(1)
                 .requires("generate"),
         )
+        .arg(
+            Arg::with_name("formula")
+                .long("formula")
+                .value_names(&["expression"])
+                .number_of_values(1)
+                .help("The formula to iterate, in terms of z and c (like `z^3 - z + c`)"),
+        )
+        .arg(
+            Arg::with_name("viewport")
+                .long("viewport")
+                .value_names(&["viewport"])
+                .number_of_values(1)
+                .help("The part of the plane to draw (like `center=-0.75+0i width=3.5 height=2`)"),
+        )
+        .arg(
+            Arg::with_name("supersample")
+                .long("supersample")
+                .value_names(&["pattern"])
+                .number_of_values(1)
+                .help("Samples per pixel: `none`, `grid:n`, `rotated:n` or `jittered:n[:seed]`"),
+        )
+        .arg(
+            Arg::with_name("adaptive")
+                .long("adaptive")
+                .value_names(&["threshold"])
+                .number_of_values(1)
+                .requires("supersample")
+                .help("Only supersample pixels that differ from a neighbour by more than this (0 to 1)"),
+        )
+        .arg(
+            Arg::with_name("palette")
+                .long("palette")
+                .value_names(&["FILE"])
+                .number_of_values(1)
+                .help("A palette file to color with (Fractint .map, Ultra Fractal .ugr or GIMP .ggr)"),
+        )
 }
 
 pub fn get_dimensions(m: &ArgMatches) -> Option<(u32, u32)> {
@@ -79,6 +119,34 @@ pub fn get_number_of_frames(m: &ArgMatches) -> Option<u32> {
     }
 }
 
+#[allow(dead_code)]
+pub fn get_formula(m: &ArgMatches) -> Option<mandelbrot::Result<Expression>> {
+    m.value_of("formula").map(Expression::parse)
+}
+
+#[allow(dead_code)]
+pub fn get_viewport(m: &ArgMatches) -> Option<mandelbrot::Result<Viewport<f64>>> {
+    m.value_of("viewport").map(str::parse)
+}
+
+#[allow(dead_code)]
+pub fn get_supersampling(m: &ArgMatches) -> Option<mandelbrot::Result<Supersampling>> {
+    m.value_of("supersample").map(str::parse)
+}
+
+#[allow(dead_code)]
+pub fn get_adaptive_threshold(m: &ArgMatches) -> Option<f64> {
+    m.value_of("adaptive").map(|val| {
+        val.parse::<f64>()
+            .unwrap_or_else(|e| panic!("Invalid adaptive threshold `{}`: {}", val, e))
+    })
+}
+
+#[allow(dead_code)]
+pub fn get_palette(m: &ArgMatches) -> Option<mandelbrot::Result<Gradient>> {
+    m.value_of("palette").map(palette::load)
+}
+
 #[allow(dead_code)]
 pub fn get_output_file(m: &ArgMatches, def: &str) -> Result<File> {
     let path_str = m.value_of("output").unwrap_or(def);
@@ -1,2 +1 @@
 pub mod cli;
-pub mod common;
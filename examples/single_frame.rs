@@ -6,13 +6,15 @@ use mandelbrot::mandelbrot::{Mandelbrot, MandelbrotConfig, Viewport};
 use png::HasParameters;
 
 mod shared;
-use shared::{cli, common};
-use std::io::BufWriter;
+use shared::cli;
+use std::{io::BufWriter, sync::Arc};
 
 use mandelbrot::complex_number;
 
 #[allow(unused_imports)]
-use mandelbrot::color_scale::{ColorScale, ContinuousColorScale, SimpleColorScale};
+use mandelbrot::color_scale::{
+    ColorScale, ContinuousColorScale, GradientColorScale, SimpleColorScale,
+};
 
 fn main() {
     let matches = cli::args().get_matches();
@@ -27,27 +29,43 @@ fn main() {
     encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
     let mut writer = encoder.write_header().unwrap();
 
-    let viewport = Viewport::<f64> {
-        top_left: complex_number::ComplexNumber::new(-3.0, 1.15),
-        width: 4f64,
-        height: (h as f64 / w as f64) * 4f64,
+    // The whole set, fit to the image (unless we're told otherwise):
+    let viewport = match cli::get_viewport(&matches) {
+        Some(viewport) => viewport.unwrap_or_else(|e| panic!("{}", e)),
+        None => Viewport::from_center_zoom(
+            complex_number::ComplexNumber::new(-1.0, 0.0),
+            1.0,
+            dimensions,
+        ),
     };
 
-    let config = MandelbrotConfig::<u8> {
+    let mut config = MandelbrotConfig::<u8>::new(
         dimensions,
         viewport,
-        // color_fn: ContinuousColorScale::get_color_fn(20.0, 0.8, 1.0),
-        color_fn: ContinuousColorScale::get_color_fn_boxed(200.0, 1.0, 1.0),
-        // color_fn: Box::new(SimpleColorScale::pixel_color),
-    };
+        // Box::new(ContinuousColorScale::new(20.0, 0.8, 1.0, 10.0).expect("Invalid colors")),
+        Box::new(ContinuousColorScale::new(200.0, 1.0, 1.0, 10.0).expect("Invalid colors")),
+        // Box::new(SimpleColorScale {}),
+    );
+
+    if let Some(formula) = cli::get_formula(&matches) {
+        config.formula = Arc::new(formula.unwrap_or_else(|e| panic!("{}", e)));
+    }
+
+    if let Some(palette) = cli::get_palette(&matches) {
+        let gradient = palette.unwrap_or_else(|e| panic!("{}", e));
+        config.color_fn = Box::new(GradientColorScale::new(gradient));
+    }
+
+    if let Some(supersampling) = cli::get_supersampling(&matches) {
+        config.supersampling = supersampling.unwrap_or_else(|e| panic!("{}", e));
+        config.adaptive_threshold = cli::get_adaptive_threshold(&matches);
+    }
 
-    let mut mandelbrot = Mandelbrot::new(config);
+    let mut mandelbrot = Mandelbrot::new(config).expect("Invalid dimensions or viewport");
 
     mandelbrot.run_iterations(50);
 
     let data = mandelbrot.get_pixels();
 
-    writer
-        .write_image_data(common::flatten_array(data).as_slice())
-        .unwrap();
+    writer.write_image_data(data.as_rgba_bytes()).unwrap();
 }
@@ -0,0 +1,1115 @@
+//! An Arbitrary Precision Floating Point Type
+//!
+//! `f64`s run out of precision at a zoom of around 1e13. `BigFloat<N>` is a
+//! software floating point number with an `N` * 64 bit mantissa and a 32 bit
+//! exponent that implements `num_traits::Float`, so it can be used anywhere
+//! an `f64` can: in a `ComplexNumber`, a `Viewport`, or a `ReferenceOrbit`.
+//!
+//! Addition, subtraction, multiplication, division and square roots are done
+//! at full precision (rounded to nearest). Everything else (trig functions,
+//! logarithms, etc.) goes through an `f64` so it's only as precise as an
+//! `f64` is.
+
+extern crate num_traits;
+
+use self::num_traits::{Float, Num, NumCast, One, ToPrimitive, Zero};
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt,
+    num::FpCategory,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+    str::FromStr,
+};
+
+/// The most limbs a `BigFloat` can have (2048 bits)
+const MAX_LIMBS: usize = 32;
+
+/// Big enough for a full product of two `BigFloat`s plus a couple of guard
+/// limbs
+const WIDE: usize = 2 * MAX_LIMBS + 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    Finite,
+    Infinite,
+    Nan,
+}
+
+/// A floating point number with an `N` * 64 bit mantissa
+///
+/// The value is `0.mantissa * 2^exponent`; the mantissa is stored most
+/// significant limb first and always has its top bit set (unless the number
+/// is zero).
+#[derive(Clone, Copy)]
+pub struct BigFloat<const N: usize> {
+    class: Class,
+    negative: bool,
+    exponent: i32,
+    mantissa: [u64; N],
+}
+
+/// The error returned when a `BigFloat` can't be parsed from a string
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseBigFloatError {
+    input: String,
+}
+
+impl fmt::Display for ParseBigFloatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid number: `{}`", self.input)
+    }
+}
+
+impl Error for ParseBigFloatError {}
+
+/// Shifts a (most significant limb first) big number left by `bits` bits
+fn shl(buf: &mut [u64], bits: usize) {
+    let (limbs, bits) = (bits / 64, bits % 64);
+    let len = buf.len();
+
+    for i in 0..len {
+        let hi = buf.get(i + limbs).cloned().unwrap_or(0);
+        let lo = buf.get(i + limbs + 1).cloned().unwrap_or(0);
+
+        buf[i] = if bits == 0 {
+            hi
+        } else {
+            (hi << bits) | (lo >> (64 - bits))
+        };
+    }
+}
+
+/// Shifts a (most significant limb first) big number right by `bits` bits
+fn shr(buf: &mut [u64], bits: usize) {
+    let (limbs, bits) = (bits / 64, bits % 64);
+
+    for i in (0..buf.len()).rev() {
+        let lo = if i >= limbs { buf[i - limbs] } else { 0 };
+        let hi = if i > limbs { buf[i - limbs - 1] } else { 0 };
+
+        buf[i] = if bits == 0 {
+            lo
+        } else {
+            (lo >> bits) | (hi << (64 - bits))
+        };
+    }
+}
+
+/// `a += b`, returning the carry out of the top limb
+fn add_in_place(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+
+    for (a, b) in a.iter_mut().zip(b.iter()).rev() {
+        let (sum, c1) = a.overflowing_add(*b);
+        let (sum, c2) = sum.overflowing_add(carry as u64);
+        *a = sum;
+        carry = c1 || c2;
+    }
+
+    carry
+}
+
+/// `a -= b`; `a` has to be at least as big as `b`
+fn sub_in_place(a: &mut [u64], b: &[u64]) {
+    let mut borrow = false;
+
+    for (a, b) in a.iter_mut().zip(b.iter()).rev() {
+        let (diff, b1) = a.overflowing_sub(*b);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        *a = diff;
+        borrow = b1 || b2;
+    }
+}
+
+/// Compares two big numbers of the same length
+fn cmp_limbs(a: &[u64], b: &[u64]) -> Ordering {
+    a.iter().cmp(b.iter())
+}
+
+/// Multiplies an `f64` by `2^exp` without overflowing along the way
+fn ldexp(mut x: f64, mut exp: i64) -> f64 {
+    while exp > 1000 {
+        x *= 2f64.powi(1000);
+        exp -= 1000;
+        if x.is_infinite() {
+            return x;
+        }
+    }
+
+    while exp < -1000 {
+        x *= 2f64.powi(-1000);
+        exp += 1000;
+        if x == 0.0 {
+            return x;
+        }
+    }
+
+    x * 2f64.powi(exp as i32)
+}
+
+impl<const N: usize> BigFloat<N> {
+    /// The number of bits in the mantissa
+    pub const BITS: u32 = 64 * N as u32;
+
+    fn special(class: Class, negative: bool) -> Self {
+        BigFloat {
+            class,
+            negative,
+            exponent: 0,
+            mantissa: [0; N],
+        }
+    }
+
+    /// Builds a `BigFloat` out of `0.buf * 2^exponent`, normalizing and
+    /// rounding `buf` to `N` limbs
+    fn from_parts(negative: bool, exponent: i64, buf: &[u64]) -> Self {
+        assert!(N >= 1 && N <= MAX_LIMBS && buf.len() <= WIDE);
+
+        let mut wide = [0u64; WIDE];
+        wide[..buf.len()].copy_from_slice(buf);
+        let wide = &mut wide[..buf.len().max(N + 1)];
+
+        let leading_zeros = match wide.iter().position(|l| *l != 0) {
+            Some(i) => i * 64 + wide[i].leading_zeros() as usize,
+            None => return Self::special(Class::Finite, negative),
+        };
+
+        shl(wide, leading_zeros);
+        let mut exponent = exponent - leading_zeros as i64;
+
+        let mut mantissa = [0u64; N];
+        mantissa.copy_from_slice(&wide[..N]);
+
+        // Round to nearest:
+        if wide[N] >> 63 == 1 {
+            let mut one = [0u64; N];
+            one[N - 1] = 1;
+
+            if add_in_place(&mut mantissa, &one) {
+                mantissa[0] = 1 << 63;
+                exponent += 1;
+            }
+        }
+
+        if exponent > (i32::MAX as i64) {
+            Self::special(Class::Infinite, negative)
+        } else if exponent < (i32::MIN as i64) {
+            Self::special(Class::Finite, negative)
+        } else {
+            BigFloat {
+                class: Class::Finite,
+                negative,
+                exponent: exponent as i32,
+                mantissa,
+            }
+        }
+    }
+
+    fn is_zero_value(&self) -> bool {
+        self.class == Class::Finite && self.mantissa[0] == 0
+    }
+
+    /// Makes a `BigFloat` from an `f64` (exactly)
+    pub fn from_f64(x: f64) -> Self {
+        if x.is_nan() {
+            return Self::special(Class::Nan, false);
+        }
+        if x.is_infinite() {
+            return Self::special(Class::Infinite, x < 0.0);
+        }
+
+        let (mantissa, exponent, sign) = x.integer_decode();
+        Self::from_parts(sign < 0, (exponent as i64) + 64, &[mantissa])
+    }
+
+    /// Makes a `BigFloat` from a `u64` (exactly)
+    pub fn from_u64(x: u64) -> Self {
+        Self::from_parts(false, 64, &[x])
+    }
+
+    /// Makes a `BigFloat` from an `i64` (exactly)
+    pub fn from_i64(x: i64) -> Self {
+        let magnitude = Self::from_u64(x.unsigned_abs());
+        if x < 0 {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Rounds this number to the nearest `f64`
+    pub fn to_f64(&self) -> f64 {
+        let sign = if self.negative { -1.0 } else { 1.0 };
+
+        match self.class {
+            Class::Nan => f64::NAN,
+            Class::Infinite => sign * f64::INFINITY,
+            Class::Finite => {
+                // Fold the rest of the mantissa into the lowest bit so the
+                // rounding comes out right:
+                let sticky = self.mantissa[1..].iter().any(|l| *l != 0) as u64;
+                let top = self.mantissa[0] | sticky;
+
+                sign * ldexp(top as f64, (self.exponent as i64) - 64)
+            }
+        }
+    }
+
+    /// Multiplies this number by `2^exp`
+    fn mul_pow2(self, exp: i64) -> Self {
+        if self.class != Class::Finite || self.is_zero_value() {
+            return self;
+        }
+
+        Self::from_parts(self.negative, (self.exponent as i64) + exp, &self.mantissa)
+    }
+
+    /// Compares the magnitudes of two finite numbers
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        match (self.is_zero_value(), other.is_zero_value()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => self
+                .exponent
+                .cmp(&other.exponent)
+                .then_with(|| cmp_limbs(&self.mantissa, &other.mantissa)),
+        }
+    }
+
+    /// Adds (or subtracts, if `subtract` is set) the magnitudes of two finite
+    /// numbers; `a` must have the bigger magnitude. The result has `a`'s sign.
+    fn add_magnitudes(a: &Self, b: &Self, subtract: bool) -> Self {
+        if b.is_zero_value() {
+            return *a;
+        }
+
+        let shift = ((a.exponent as i64) - (b.exponent as i64)) as usize;
+        if shift > 64 * (N + 2) {
+            return *a;
+        }
+
+        // One limb of headroom for carries and one (extra) guard limb:
+        let mut x = [0u64; WIDE];
+        let mut y = [0u64; WIDE];
+        x[1..=N].copy_from_slice(&a.mantissa);
+        y[1..=N].copy_from_slice(&b.mantissa);
+
+        let (x, y) = (&mut x[..N + 3], &mut y[..N + 3]);
+        shr(y, shift);
+
+        if subtract {
+            sub_in_place(x, y);
+        } else {
+            add_in_place(x, y);
+        }
+
+        Self::from_parts(a.negative, (a.exponent as i64) + 64, x)
+    }
+
+    /// Finds the square root with Newton's method
+    fn sqrt_newton(self) -> Self {
+        // Pull the exponent out (keeping it even) so the first guess can be
+        // made with an `f64` no matter how big or small this number is:
+        let half = (self.exponent as i64) / 2;
+        let scaled = self.mul_pow2(-2 * half);
+
+        let mut x = Self::from_f64(scaled.to_f64().sqrt());
+        let half_one = Self::from_f64(0.5);
+
+        let mut precision = 50;
+        while precision < 2 * Self::BITS {
+            x = half_one * (x + scaled / x);
+            precision *= 2;
+        }
+
+        x.mul_pow2(half)
+    }
+}
+
+impl<const N: usize> PartialEq for BigFloat<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl<const N: usize> PartialOrd for BigFloat<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.class == Class::Nan || other.class == Class::Nan {
+            return None;
+        }
+
+        let sign = |n: &Self| {
+            if n.class == Class::Finite && n.is_zero_value() {
+                0
+            } else if n.negative {
+                -1
+            } else {
+                1
+            }
+        };
+
+        let (s, o) = (sign(self), sign(other));
+        if s != o {
+            return Some(s.cmp(&o));
+        }
+
+        let magnitude = match (self.class, other.class) {
+            (Class::Infinite, Class::Infinite) => Ordering::Equal,
+            (Class::Infinite, _) => Ordering::Greater,
+            (_, Class::Infinite) => Ordering::Less,
+            _ => self.cmp_magnitude(other),
+        };
+
+        Some(if s < 0 {
+            magnitude.reverse()
+        } else {
+            magnitude
+        })
+    }
+}
+
+impl<const N: usize> Neg for BigFloat<N> {
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        self.negative = !self.negative;
+        self
+    }
+}
+
+impl<const N: usize> Add for BigFloat<N> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        match (self.class, other.class) {
+            (Class::Nan, _) | (_, Class::Nan) => Self::nan(),
+            (Class::Infinite, Class::Infinite) if self.negative != other.negative => Self::nan(),
+            (Class::Infinite, _) => self,
+            (_, Class::Infinite) => other,
+            _ => {
+                let (a, b) = if self.cmp_magnitude(&other) == Ordering::Less {
+                    (other, self)
+                } else {
+                    (self, other)
+                };
+
+                Self::add_magnitudes(&a, &b, a.negative != b.negative)
+            }
+        }
+    }
+}
+
+impl<const N: usize> Sub for BigFloat<N> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl<const N: usize> Mul for BigFloat<N> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let negative = self.negative != other.negative;
+
+        match (self.class, other.class) {
+            (Class::Nan, _) | (_, Class::Nan) => Self::nan(),
+            (Class::Infinite, _) | (_, Class::Infinite) => {
+                if self.is_zero_value() || other.is_zero_value() {
+                    Self::nan()
+                } else {
+                    Self::special(Class::Infinite, negative)
+                }
+            }
+            _ => {
+                let mut product = [0u64; WIDE];
+
+                for i in (0..N).rev() {
+                    let mut carry: u128 = 0;
+
+                    for j in (0..N).rev() {
+                        let t = (self.mantissa[i] as u128) * (other.mantissa[j] as u128)
+                            + (product[i + j + 1] as u128)
+                            + carry;
+                        product[i + j + 1] = t as u64;
+                        carry = t >> 64;
+                    }
+
+                    product[i] = carry as u64;
+                }
+
+                Self::from_parts(
+                    negative,
+                    (self.exponent as i64) + (other.exponent as i64),
+                    &product[..2 * N],
+                )
+            }
+        }
+    }
+}
+
+impl<const N: usize> Div for BigFloat<N> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let negative = self.negative != other.negative;
+
+        match (self.class, other.class) {
+            (Class::Nan, _) | (_, Class::Nan) => Self::nan(),
+            (Class::Infinite, Class::Infinite) => Self::nan(),
+            (Class::Infinite, _) => Self::special(Class::Infinite, negative),
+            (_, Class::Infinite) => Self::special(Class::Finite, negative),
+            _ if other.is_zero_value() => {
+                if self.is_zero_value() {
+                    Self::nan()
+                } else {
+                    Self::special(Class::Infinite, negative)
+                }
+            }
+            _ if self.is_zero_value() => Self::special(Class::Finite, negative),
+            _ => {
+                // Long division, one bit at a time; the remainder gets a limb
+                // of headroom so it can be shifted without losing anything:
+                let mut remainder = [0u64; WIDE];
+                let mut divisor = [0u64; WIDE];
+                let mut quotient = [0u64; WIDE];
+                remainder[1..=N].copy_from_slice(&self.mantissa);
+                divisor[1..=N].copy_from_slice(&other.mantissa);
+
+                let (remainder, divisor) = (&mut remainder[..N + 1], &divisor[..N + 1]);
+                let quotient = &mut quotient[..N + 1];
+
+                for bit in 0..64 * (N + 1) {
+                    if cmp_limbs(remainder, divisor) != Ordering::Less {
+                        sub_in_place(remainder, divisor);
+                        quotient[bit / 64] |= 1 << (63 - bit % 64);
+                    }
+
+                    shl(remainder, 1);
+                }
+
+                // The first quotient bit is the ones place:
+                Self::from_parts(
+                    negative,
+                    (self.exponent as i64) - (other.exponent as i64) + 1,
+                    quotient,
+                )
+            }
+        }
+    }
+}
+
+impl<const N: usize> Rem for BigFloat<N> {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self - (self / other).trunc() * other
+    }
+}
+
+impl<const N: usize> Zero for BigFloat<N> {
+    fn zero() -> Self {
+        Self::special(Class::Finite, false)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.is_zero_value()
+    }
+}
+
+impl<const N: usize> One for BigFloat<N> {
+    fn one() -> Self {
+        Self::from_u64(1)
+    }
+}
+
+impl<const N: usize> Num for BigFloat<N> {
+    type FromStrRadixErr = ParseBigFloatError;
+
+    /// Only base 10 is supported
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseBigFloatError> {
+        if radix == 10 {
+            s.parse()
+        } else {
+            Err(ParseBigFloatError {
+                input: s.to_string(),
+            })
+        }
+    }
+}
+
+impl<const N: usize> ToPrimitive for BigFloat<N> {
+    fn to_i64(&self) -> Option<i64> {
+        let magnitude = self.abs().to_u64()?;
+
+        if self.negative {
+            if magnitude <= i64::MAX as u64 + 1 {
+                Some((magnitude as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else if magnitude <= i64::MAX as u64 {
+            Some(magnitude as i64)
+        } else {
+            None
+        }
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        let t = self.trunc();
+
+        if t.class != Class::Finite || (t.negative && !t.is_zero_value()) {
+            None
+        } else if t.is_zero_value() || t.exponent <= 0 {
+            Some(0)
+        } else if t.exponent > 64 {
+            None
+        } else {
+            Some(t.mantissa[0] >> (64 - t.exponent))
+        }
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(BigFloat::to_f64(self))
+    }
+}
+
+impl<const N: usize> NumCast for BigFloat<N> {
+    fn from<T: ToPrimitive>(n: T) -> Option<Self> {
+        let f = n.to_f64()?;
+
+        // Integers too big for an `f64` to hold exactly:
+        if f.abs() >= 2f64.powi(53) && f.fract() == 0.0 {
+            if let Some(i) = n.to_i64() {
+                return Some(Self::from_i64(i));
+            }
+            if let Some(u) = n.to_u64() {
+                return Some(Self::from_u64(u));
+            }
+        }
+
+        Some(Self::from_f64(f))
+    }
+}
+
+/// Functions that aren't computed at full precision go through an `f64`
+macro_rules! via_f64 {
+    ($($name:ident),*) => {
+        $(
+            fn $name(self) -> Self {
+                Self::from_f64(BigFloat::to_f64(&self).$name())
+            }
+        )*
+    };
+}
+
+impl<const N: usize> Float for BigFloat<N> {
+    fn nan() -> Self {
+        Self::special(Class::Nan, false)
+    }
+
+    fn infinity() -> Self {
+        Self::special(Class::Infinite, false)
+    }
+
+    fn neg_infinity() -> Self {
+        Self::special(Class::Infinite, true)
+    }
+
+    fn neg_zero() -> Self {
+        Self::special(Class::Finite, true)
+    }
+
+    fn min_value() -> Self {
+        -Self::max_value()
+    }
+
+    fn min_positive_value() -> Self {
+        let mut mantissa = [0; N];
+        mantissa[0] = 1 << 63;
+
+        BigFloat {
+            class: Class::Finite,
+            negative: false,
+            exponent: i32::MIN,
+            mantissa,
+        }
+    }
+
+    fn epsilon() -> Self {
+        Self::one().mul_pow2(1 - (Self::BITS as i64))
+    }
+
+    fn max_value() -> Self {
+        BigFloat {
+            class: Class::Finite,
+            negative: false,
+            exponent: i32::MAX,
+            mantissa: [u64::MAX; N],
+        }
+    }
+
+    fn is_nan(self) -> bool {
+        self.class == Class::Nan
+    }
+
+    fn is_infinite(self) -> bool {
+        self.class == Class::Infinite
+    }
+
+    fn is_finite(self) -> bool {
+        self.class == Class::Finite
+    }
+
+    fn is_normal(self) -> bool {
+        self.class == Class::Finite && !self.is_zero_value()
+    }
+
+    fn classify(self) -> FpCategory {
+        match self.class {
+            Class::Nan => FpCategory::Nan,
+            Class::Infinite => FpCategory::Infinite,
+            Class::Finite if self.is_zero_value() => FpCategory::Zero,
+            Class::Finite => FpCategory::Normal,
+        }
+    }
+
+    fn floor(self) -> Self {
+        let t = self.trunc();
+
+        if self.negative && t != self {
+            t - Self::one()
+        } else {
+            t
+        }
+    }
+
+    fn ceil(self) -> Self {
+        let t = self.trunc();
+
+        if !self.negative && t != self {
+            t + Self::one()
+        } else {
+            t
+        }
+    }
+
+    fn round(self) -> Self {
+        let half = Self::from_f64(0.5);
+        let t = self.trunc();
+
+        if (self - t).abs() >= half {
+            t + self.signum()
+        } else {
+            t
+        }
+    }
+
+    fn trunc(self) -> Self {
+        if self.class != Class::Finite || self.exponent >= Self::BITS as i32 {
+            return self;
+        }
+        if self.exponent <= 0 {
+            return Self::special(Class::Finite, self.negative);
+        }
+
+        let mut t = self;
+        let keep = self.exponent as usize;
+
+        for (i, limb) in t.mantissa.iter_mut().enumerate() {
+            let start = i * 64;
+
+            if start >= keep {
+                *limb = 0;
+            } else if start + 64 > keep {
+                *limb &= !(u64::MAX >> (keep - start));
+            }
+        }
+
+        t
+    }
+
+    fn fract(self) -> Self {
+        self - self.trunc()
+    }
+
+    fn abs(mut self) -> Self {
+        self.negative = false;
+        self
+    }
+
+    fn signum(self) -> Self {
+        match self.class {
+            Class::Nan => self,
+            _ if self.negative => -Self::one(),
+            _ => Self::one(),
+        }
+    }
+
+    fn is_sign_positive(self) -> bool {
+        !self.negative
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.negative
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        Self::one() / self
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let mut result = Self::one();
+        let mut base = self;
+        let mut exp = (n as i64).abs();
+
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exp >>= 1;
+        }
+
+        if n < 0 {
+            result.recip()
+        } else {
+            result
+        }
+    }
+
+    fn powf(self, n: Self) -> Self {
+        Self::from_f64(BigFloat::to_f64(&self).powf(BigFloat::to_f64(&n)))
+    }
+
+    fn sqrt(self) -> Self {
+        match self.class {
+            Class::Nan => self,
+            _ if self.is_zero_value() => self,
+            _ if self.negative => Self::nan(),
+            Class::Infinite => self,
+            Class::Finite => self.sqrt_newton(),
+        }
+    }
+
+    /// This one is done as `ln(mantissa) + exponent * ln(2)` so that it works
+    /// for numbers that are too big or too small for an `f64`
+    fn ln(self) -> Self {
+        if self.class != Class::Finite || self.negative || self.is_zero_value() {
+            return Self::from_f64(BigFloat::to_f64(&self).ln());
+        }
+
+        let mantissa = self.mul_pow2(-(self.exponent as i64));
+        Self::from_f64(BigFloat::to_f64(&mantissa).ln() + (self.exponent as f64) * 2f64.ln())
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+
+    fn log2(self) -> Self {
+        self.ln() / Self::from_f64(2f64.ln())
+    }
+
+    fn log10(self) -> Self {
+        self.ln() / Self::from_f64(10f64.ln())
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.is_nan() || other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.is_nan() || other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        if self <= other {
+            Self::zero()
+        } else {
+            self - other
+        }
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        (self * self + other * other).sqrt()
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        Self::from_f64(BigFloat::to_f64(&self).atan2(BigFloat::to_f64(&other)))
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        BigFloat::to_f64(&self).integer_decode()
+    }
+
+    via_f64!(
+        exp, exp2, cbrt, sin, cos, tan, asin, acos, atan, exp_m1, ln_1p, sinh, cosh, tanh, asinh,
+        acosh, atanh
+    );
+}
+
+impl<const N: usize> FromStr for BigFloat<N> {
+    type Err = ParseBigFloatError;
+
+    /// Parses decimal numbers, with an optional sign, fractional part and
+    /// exponent (`-12.5e-3`); `inf`, `infinity` and `nan` work too
+    fn from_str(s: &str) -> Result<Self, ParseBigFloatError> {
+        let err = || ParseBigFloatError {
+            input: s.to_string(),
+        };
+
+        let (negative, rest) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let sign = |n: Self| if negative { -n } else { n };
+
+        match rest.to_ascii_lowercase().as_str() {
+            "inf" | "infinity" => return Ok(sign(Self::infinity())),
+            "nan" => return Ok(Self::nan()),
+            _ => {}
+        }
+
+        let (digits, exponent) = match rest.find(['e', 'E']) {
+            Some(i) => (&rest[..i], rest[i + 1..].parse::<i32>().map_err(|_| err())?),
+            None => (rest, 0),
+        };
+
+        let (int, frac) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits, ""),
+        };
+
+        if int.is_empty() && frac.is_empty() {
+            return Err(err());
+        }
+
+        let ten = Self::from_u64(10);
+        let mut value = Self::zero();
+
+        for c in int.chars().chain(frac.chars()) {
+            let digit = c.to_digit(10).ok_or_else(err)?;
+            value = value * ten + Self::from_u64(digit as u64);
+        }
+
+        let exponent = (exponent as i64) - frac.len() as i64;
+        if exponent.abs() > (i32::MAX as i64) {
+            return Err(err());
+        }
+
+        let scale = ten.powi(exponent.abs() as i32);
+        Ok(sign(if exponent < 0 {
+            value / scale
+        } else {
+            value * scale
+        }))
+    }
+}
+
+impl<const N: usize> fmt::Display for BigFloat<N> {
+    /// Prints in scientific notation (`-1.25e-3`) with as many digits as the
+    /// mantissa can hold
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.class {
+            Class::Nan => return write!(f, "NaN"),
+            Class::Infinite if self.negative => return write!(f, "-inf"),
+            Class::Infinite => return write!(f, "inf"),
+            Class::Finite if self.is_zero_value() => {
+                return write!(f, "{}0", if self.negative { "-" } else { "" })
+            }
+            Class::Finite => {}
+        }
+
+        let ten = Self::from_u64(10);
+        let one = Self::one();
+
+        let mut exponent = BigFloat::to_f64(&self.abs().log10()).floor() as i32;
+        let mut y = if exponent < 0 {
+            self.abs() * ten.powi(-exponent)
+        } else {
+            self.abs() / ten.powi(exponent)
+        };
+
+        // The estimate can be off by one either way:
+        while y >= ten {
+            y = y / ten;
+            exponent += 1;
+        }
+        while y < one {
+            y = y * ten;
+            exponent -= 1;
+        }
+
+        let num_digits = ((Self::BITS as f64) * 2f64.log10()) as usize + 1;
+        let mut digits = String::with_capacity(num_digits + 1);
+
+        for _ in 0..num_digits {
+            let d = y.to_u64().unwrap_or(0).min(9);
+            digits.push((b'0' + d as u8) as char);
+            y = (y - Self::from_u64(d)) * ten;
+        }
+
+        let digits = digits.trim_end_matches('0');
+        let (first, rest) = digits.split_at(1);
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        if rest.is_empty() {
+            write!(f, "{}e{}", first, exponent)
+        } else {
+            write!(f, "{}.{}e{}", first, rest, exponent)
+        }
+    }
+}
+
+impl<const N: usize> fmt::Debug for BigFloat<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use big_float::num_traits::{Float, One, ToPrimitive, Zero};
+    use big_float::BigFloat;
+
+    type B4 = BigFloat<4>;
+
+    fn b(x: f64) -> B4 {
+        B4::from_f64(x)
+    }
+
+    #[test]
+    fn f64_round_trip() {
+        for x in [
+            0.0,
+            1.0,
+            -1.0,
+            0.1,
+            -3.75,
+            1e300,
+            1e-300,
+            5e-324,
+            123456789.0,
+        ]
+        .iter()
+        {
+            assert_eq!(b(*x).to_f64(), *x);
+        }
+
+        assert!(b(f64::NAN).to_f64().is_nan());
+        assert_eq!(b(f64::INFINITY).to_f64(), f64::INFINITY);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!((b(1.5) + b(2.25)).to_f64(), 3.75);
+        assert_eq!((b(1.5) - b(2.25)).to_f64(), -0.75);
+        assert_eq!((b(1.5) * b(-2.25)).to_f64(), -3.375);
+        assert_eq!((b(-3.375) / b(1.5)).to_f64(), -2.25);
+        assert_eq!((b(7.5) % b(2.0)).to_f64(), 1.5);
+        assert_eq!((b(2.0) - b(2.0)).to_f64(), 0.0);
+        assert!((b(1.0) / b(0.0)).is_infinite());
+        assert!((b(0.0) / b(0.0)).is_nan());
+    }
+
+    #[test]
+    fn more_precise_than_f64() {
+        // 2^-200 disappears when added to 1 as an f64, but not here:
+        let tiny = B4::one() / b(2.0).powi(200);
+        let sum = B4::one() + tiny;
+
+        assert!(sum > B4::one());
+        assert_eq!(sum - B4::one(), tiny);
+
+        // (1/3) * 3 should be off by no more than a couple of units in the
+        // last place:
+        let third = B4::one() / b(3.0);
+        let error = (third * b(3.0) - B4::one()).abs();
+        assert!(error <= B4::epsilon());
+    }
+
+    #[test]
+    fn sqrt() {
+        let two = b(2.0);
+        let root = two.sqrt();
+
+        assert_eq!(root.to_f64(), 2f64.sqrt());
+        assert!((root * root - two).abs() <= B4::epsilon() * b(4.0));
+
+        assert_eq!(b(2f64.powi(-1000)).sqrt().to_f64(), 2f64.powi(-500));
+        assert!(b(-1.0).sqrt().is_nan());
+    }
+
+    #[test]
+    fn rounding_functions() {
+        assert_eq!(b(2.5).floor().to_f64(), 2.0);
+        assert_eq!(b(-2.5).floor().to_f64(), -3.0);
+        assert_eq!(b(2.5).ceil().to_f64(), 3.0);
+        assert_eq!(b(-2.5).ceil().to_f64(), -2.0);
+        assert_eq!(b(2.5).round().to_f64(), 3.0);
+        assert_eq!(b(-2.4).round().to_f64(), -2.0);
+        assert_eq!(b(-2.75).trunc().to_f64(), -2.0);
+        assert_eq!(b(-2.75).fract().to_f64(), -0.75);
+        assert_eq!(b(0.75).trunc().to_f64(), 0.0);
+        assert_eq!(b(12345.0).to_u64(), Some(12345));
+        assert_eq!(b(-12345.5).to_i64(), Some(-12345));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(b(1.0) < b(2.0));
+        assert!(b(-1.0) > b(-2.0));
+        assert!(b(-1.0) < b(0.0));
+        assert!(b(0.0) == B4::neg_zero());
+        assert!(b(1e300) < B4::infinity());
+        assert!(B4::nan() != B4::nan());
+        assert!(B4::zero().is_zero());
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let x: B4 = "-0.743643887037158704752191506114774".parse().unwrap();
+        assert_eq!(x.to_f64(), -0.7436438870371587);
+        assert!(x
+            .to_string()
+            .starts_with("-7.436438870371587047521915061147"));
+        assert!(x.to_string().ends_with("e-1"));
+
+        let y: B4 = x.to_string().parse().unwrap();
+        assert!((x - y).abs() <= B4::epsilon() * b(4.0));
+
+        assert_eq!("1e-400".parse::<B4>().unwrap().to_string(), "1e-400");
+        assert_eq!("25".parse::<B4>().unwrap().to_f64(), 25.0);
+        assert_eq!(".5".parse::<B4>().unwrap().to_f64(), 0.5);
+        assert!("1.2.3".parse::<B4>().is_err());
+        assert!("".parse::<B4>().is_err());
+        assert!("-".parse::<B4>().is_err());
+        assert!("12a".parse::<B4>().is_err());
+    }
+}
@@ -1,147 +1,648 @@
 extern crate num_traits;
 
 use self::num_traits::{AsPrimitive, Bounded, Unsigned};
-use complex_number::ComplexNumber;
+use error::{Error, Result};
+use gradient::{Color, Gradient, Interpolation, Wrap};
+use mandelbrot::{Orbit, RenderInfo};
 use pixel::{Pixel, PixelMath};
 use std::fmt::UpperHex;
 
-pub trait ColorScale {
-    fn pixel_color<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
-        iters_to_escape: u32,
-        ending_point: ComplexNumber<f64>,
-        num_iterations: u32,
-    ) -> Pixel<T>
-    where
-        f64: From<T> + AsPrimitive<T>;
+/// Something that colors in points: given a point's `Orbit` and the
+/// `RenderInfo` for the frame (which has statistics about the whole frame in
+/// it), it picks the point's color
+///
+/// Color scales carry their settings with them and are object safe, so
+/// `MandelbrotConfig` holds one as a `Box<dyn ColorScale<P>>`. Closures that
+/// take an `Orbit` and a `RenderInfo` are color scales too.
+pub trait ColorScale<T: Unsigned + Bounded> {
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T>;
 }
 
-pub struct ContinuousColorScale {}
+impl<T: Unsigned + Bounded, F> ColorScale<T> for F
+where
+    F: Fn(&Orbit, &RenderInfo) -> Pixel<T>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        self(orbit, info)
+    }
+}
 
-impl ColorScale for ContinuousColorScale {
-    fn pixel_color<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
-        iters_to_escape: u32,
-        ending_point: ComplexNumber<f64>,
-        num_iterations: u32,
-    ) -> Pixel<T>
-    where
-        f64: From<T> + AsPrimitive<T>,
-    {
-        ContinuousColorScale::pixel_color_gen(
-            iters_to_escape,
-            ending_point,
-            num_iterations,
-            200.95,
-            0.8,
-            1.0,
-            10.0,
-        )
+impl<T: Unsigned + Bounded> ColorScale<T> for Box<dyn ColorScale<T>> {
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        (**self).pixel_color(orbit, info)
     }
 }
 
+/// Colors points using their smoothed (fractional) iteration counts:
+/// `n + 1 - log_d(log10(|z|))`, where `d` is the degree of the formula (2 for
+/// the usual Mandelbrot Set), with the hue going around by `scale` degrees
+/// per iteration.
+///
+/// This form of the smoothed count doesn't depend on the escape radius (each
+/// extra iteration roughly raises `|z|` to the `d`th power, which the double
+/// log undoes), so bigger escape radii just make it more accurate instead of
+/// shifting the colors around.
+///
+/// Points whose smoothed count can't be worked out (ones that never escaped)
+/// are colored black.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContinuousColorScale {
+    hue: f64,
+    sat: f64,
+    val: f64,
+    scale: f64,
+}
+
 impl ContinuousColorScale {
-    pub fn pixel_color_gen<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
-        iters_to_escape: u32,
-        ending_point: ComplexNumber<f64>,
-        num_iterations: u32,
-        hue: f64,
-        sat: f64,
-        val: f64,
-        scale: f64,
-    ) -> Pixel<T>
-    where
-        f64: From<T> + AsPrimitive<T>,
-    {
-        if iters_to_escape == num_iterations {
-            return Pixel::new(T::zero(), T::zero(), T::zero());
+    /// Fails if `sat` or `val` aren't between 0 and 1 or if `hue` or `scale`
+    /// aren't finite (or are so big that the hue for a point stops being
+    /// finite).
+    pub fn new(hue: f64, sat: f64, val: f64, scale: f64) -> Result<ContinuousColorScale> {
+        check_hsb(hue, sat, val)?;
+
+        // This is as far around as the hue can go:
+        if !scale.is_finite() || !(hue.abs() + scale.abs() * MAX_SMOOTH_ITERATIONS).is_finite() {
+            return Err(Error::InvalidConfig(
+                "the hue scale has to be finite (and not enormous)",
+            ));
         }
 
-        let smooth: f64 = iters_to_escape.into();
-        let smooth: f64 = smooth + 1.0 - ending_point.abs().log(10.0).log(2.0);
+        Ok(ContinuousColorScale {
+            hue,
+            sat,
+            val,
+            scale,
+        })
+    }
+}
+
+impl Default for ContinuousColorScale {
+    fn default() -> ContinuousColorScale {
+        ContinuousColorScale::new(200.95, 0.8, 1.0, 10.0).expect("the default colors are valid")
+    }
+}
+
+impl<T> ColorScale<T> for ContinuousColorScale
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+    f64: From<T> + AsPrimitive<T>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        let black = Pixel::new(T::zero(), T::zero(), T::zero());
+
+        if orbit.iterations == info.max_iterations {
+            return black;
+        }
+
+        match smooth_iterations(orbit, info) {
+            Some(smooth) => Pixel::from_hsb(self.hue + self.scale * smooth, self.sat, self.val)
+                .expect("the colors and the scale were checked when the color scale was made"),
+            None => black,
+        }
+    }
+}
+
+/// A bound on smoothed iteration counts: `u32::MAX` iterations plus plenty of
+/// room for the fractional part
+const MAX_SMOOTH_ITERATIONS: f64 = 8_589_934_592.0;
 
-        Pixel::from_hsb(hue + scale * smooth, sat, val).unwrap()
+/// The smoothed (fractional) iteration count of a point that escaped (see
+/// `ContinuousColorScale`), if it comes out finite and less than
+/// `MAX_SMOOTH_ITERATIONS` (in size)
+fn smooth_iterations(orbit: &Orbit, info: &RenderInfo) -> Option<f64> {
+    let smooth: f64 = orbit.iterations.into();
+    let smooth: f64 = smooth + 1.0 - orbit.z.abs().log(10.0).log(info.degree);
+
+    if smooth.abs() < MAX_SMOOTH_ITERATIONS {
+        Some(smooth)
+    } else {
+        None
     }
+}
 
-    pub fn get_color_fn<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
+/// How far through its last iteration a point that escaped got, from 0 (when
+/// `|z|` overshot the escape radius `R` all the way to `R^d`) to 1 (when it
+/// only just got past `R`): `1 - log_d(ln|z| / ln R)`, kept in that range
+///
+/// Added to the iteration count, this keeps a point within its own
+/// histogram bin (from `n` to `n + 1`) whatever the escape radius, which
+/// the radius-independent smoothed count doesn't.
+fn escape_fraction(orbit: &Orbit, info: &RenderInfo) -> f64 {
+    let overshoot = orbit.z.abs().ln() / info.escape_radius.ln();
+    let fraction = 1.0 - overshoot.log(info.degree);
+
+    if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+/// Checks HSB values up front (with a pixel type that can hold them) so
+/// color scales can't fail partway through a render
+fn check_hsb(hue: f64, sat: f64, val: f64) -> Result<()> {
+    Pixel::<u8>::from_hsb(hue, sat, val).map(|_| ())
+}
+
+/// Colors points by how their iteration counts rank against the rest of the
+/// points that escaped, using the `Histogram` in the `RenderInfo` (so
+/// `MandelbrotConfig::histogram` should be turned on)
+///
+/// The hue goes from `hue` for the points that escaped first to
+/// `hue + range` for the ones that escaped last, with each color getting
+/// about as many pixels as any other. With `smooth`, the points with each
+/// iteration count are spread over that count's share of the ranks by how
+/// far past the escape radius they ended up, which gets rid of the bands.
+///
+/// Without a histogram, points are placed by where their iteration counts
+/// fall between the fewest and the most iterations any point in the frame
+/// took to escape (see `FrameStats`). Points in the set are colored black.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistogramColorScale {
+    hue: f64,
+    sat: f64,
+    val: f64,
+    range: f64,
+    smooth: bool,
+}
+
+impl HistogramColorScale {
+    /// Fails if `sat` or `val` aren't between 0 and 1 or if `hue` or `range`
+    /// aren't finite.
+    pub fn new(
         hue: f64,
         sat: f64,
         val: f64,
-    ) -> impl Fn(u32, ComplexNumber<f64>, u32) -> Pixel<T>
-    where
-        f64: From<T> + AsPrimitive<T>,
-    {
-        move |iters_to_escape: u32,
-              ending_point: ComplexNumber<f64>,
-              num_iterations: u32|
-              -> Pixel<T> {
-            ContinuousColorScale::pixel_color_gen(
-                iters_to_escape,
-                ending_point,
-                num_iterations,
+        range: f64,
+        smooth: bool,
+    ) -> Result<HistogramColorScale> {
+        check_hsb(hue, sat, val)?;
+        check_hsb(hue + range, sat, val)?;
+
+        Ok(HistogramColorScale {
+            hue,
+            sat,
+            val,
+            range,
+            smooth,
+        })
+    }
+}
+
+impl Default for HistogramColorScale {
+    fn default() -> HistogramColorScale {
+        HistogramColorScale::new(200.95, 0.8, 1.0, 300.0, true)
+            .expect("the default colors are valid")
+    }
+}
+
+impl<T> ColorScale<T> for HistogramColorScale
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+    f64: From<T> + AsPrimitive<T>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        let black = Pixel::new(T::zero(), T::zero(), T::zero());
+
+        if orbit.iterations == info.max_iterations {
+            return black;
+        }
+
+        let rank = match info.histogram {
+            Some(histogram) if self.smooth => {
+                let iterations: f64 = orbit.iterations.into();
+                histogram.rank(iterations + escape_fraction(orbit, info))
+            }
+            Some(histogram) => histogram.rank_of(orbit),
+            None => {
+                let stats = &info.stats;
+                let spread = stats.max_escape_iterations - stats.min_escape_iterations;
+                let above = orbit.iterations.saturating_sub(stats.min_escape_iterations);
+
+                if spread == 0 {
+                    0.0
+                } else {
+                    (above as f64 / spread as f64).min(1.0)
+                }
+            }
+        };
+
+        Pixel::from_hsb(self.hue + self.range * rank, self.sat, self.val).unwrap_or(black)
+    }
+}
+
+/// Colors points by looking their smoothed iteration counts (like
+/// `ContinuousColorScale` uses) up in a `Gradient`
+///
+/// The gradient's density and offset decide how many iterations one trip
+/// through the gradient takes and where it starts; a density of `1 / 24`
+/// with `Wrap::Repeat` goes through the gradient once every 24 iterations.
+/// The default gradient is a blue, white and orange one (blended in OKLab)
+/// that repeats every 24 iterations.
+///
+/// Points in the set are colored black, as are points whose smoothed count
+/// can't be worked out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientColorScale {
+    pub gradient: Gradient,
+}
+
+impl GradientColorScale {
+    pub fn new(gradient: Gradient) -> GradientColorScale {
+        GradientColorScale { gradient }
+    }
+}
+
+impl Default for GradientColorScale {
+    fn default() -> GradientColorScale {
+        let mut gradient = Gradient::new(vec![
+            (0.0, Color::from_rgb8(0, 7, 100)),
+            (0.16, Color::from_rgb8(32, 107, 203)),
+            (0.42, Color::from_rgb8(237, 255, 255)),
+            (0.6425, Color::from_rgb8(255, 170, 0)),
+            (0.8575, Color::from_rgb8(0, 2, 0)),
+            (1.0, Color::from_rgb8(0, 7, 100)),
+        ])
+        .expect("the default stops are valid");
+
+        gradient.interpolation = Interpolation::OkLab;
+        gradient.wrap = Wrap::Repeat;
+        gradient.density = 1.0 / 24.0;
+
+        GradientColorScale::new(gradient)
+    }
+}
+
+impl<T> ColorScale<T> for GradientColorScale
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+    f64: From<T> + AsPrimitive<T>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        let black = Pixel::new(T::zero(), T::zero(), T::zero());
+
+        if orbit.iterations == info.max_iterations {
+            return black;
+        }
+
+        match smooth_iterations(orbit, info) {
+            Some(smooth) => self.gradient.pixel_at(smooth),
+            None => black,
+        }
+    }
+}
+
+/// Colors points by how far they are from the set, in pixels, using
+/// `Orbit::distance_estimate` (so distance estimation has to be turned on in
+/// the `MandelbrotConfig`)
+///
+/// Points within `line_width` pixels of the set are drawn as a dark line
+/// (which fades out over one pixel, so lines stay crisp at any zoom) and
+/// points near the set get a glow in the hue given that fades out over about
+/// `glow` pixels. Everything else is white and the set itself is black.
+///
+/// Points without a distance estimate (ones that didn't escape, or all of
+/// them if distance estimation is off) are colored black if they're in the
+/// set and white otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceColorScale {
+    hue: f64,
+    line_width: f64,
+    glow: f64,
+}
+
+impl DistanceColorScale {
+    /// Fails if the hue isn't finite or if `line_width` or `glow` are
+    /// negative or NaN.
+    pub fn new(hue: f64, line_width: f64, glow: f64) -> Result<DistanceColorScale> {
+        check_hsb(hue, 1.0, 1.0)?;
+
+        if line_width >= 0.0 && glow >= 0.0 {
+            Ok(DistanceColorScale {
                 hue,
-                sat,
-                val,
-                10.0,
-            )
+                line_width,
+                glow,
+            })
+        } else {
+            Err(Error::InvalidConfig(
+                "the line width and glow can't be negative or NaN",
+            ))
+        }
+    }
+}
+
+impl Default for DistanceColorScale {
+    fn default() -> DistanceColorScale {
+        DistanceColorScale::new(200.0, 1.0, 16.0).expect("the default settings are valid")
+    }
+}
+
+impl<T> ColorScale<T> for DistanceColorScale
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+    f64: From<T> + AsPrimitive<T>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        let hsb = |saturation: f64, brightness: f64| {
+            Pixel::from_hsb(self.hue, saturation, brightness)
+                .expect("the hue was checked when the color scale was made")
+        };
+
+        if orbit.iterations == info.max_iterations {
+            return hsb(0.0, 0.0);
         }
+
+        let distance = match orbit.distance_estimate(info.degree) {
+            Some(distance) => distance / info.pixel_size,
+            None => return hsb(0.0, 1.0),
+        };
+
+        // The line goes from black to nothing over the pixel around its edge:
+        let brightness = (distance - self.line_width + 0.5).clamp(0.0, 1.0);
+        let saturation = if self.glow > 0.0 {
+            (-distance / self.glow).exp()
+        } else {
+            0.0
+        };
+
+        hsb(saturation, brightness)
     }
+}
+
+/// Ways to color the points inside the set (which the color scales above
+/// just paint a single color)
+///
+/// Apart from `Magnitude`, these need values the iteration engine only
+/// records for points it finds settling into a cycle, so the periodicity
+/// check (or the interior check) has to be on; points without the value a
+/// mode needs, or where it isn't finite (as formulas that divide by `z` can
+/// leave it), are colored black.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InteriorColoring {
+    /// Flat black
+    Black,
+    /// By `Orbit::interior_distance`, in pixels: dark at the edge of the set
+    /// and brighter further in (distance estimation has to be on)
+    Distance,
+    /// By where the orbit ended up (`|z|`)
+    Magnitude,
+    /// By the period of the cycle the orbit settled into; each period gets
+    /// its own hue
+    Period,
+    /// By the phase of the cycle's multiplier (with the brightness going
+    /// down as its magnitude goes up), which shows the internal angles of
+    /// each component
+    MultiplierPhase,
+}
 
-    pub fn get_color_fn_boxed<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
+impl InteriorColoring {
+    /// Colors a point inside the set, shifting the hues used from `hue`
+    ///
+    /// Fails if the hue isn't finite.
+    pub fn pixel_color<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
+        self,
+        orbit: &Orbit,
+        info: &RenderInfo,
         hue: f64,
-        sat: f64,
-        val: f64,
-    ) -> Box<Fn(u32, ComplexNumber<f64>, u32) -> Pixel<T>>
+    ) -> Result<Pixel<T>>
     where
         f64: From<T> + AsPrimitive<T>,
     {
-        Box::new(
-            move |iters_to_escape: u32,
-                  ending_point: ComplexNumber<f64>,
-                  num_iterations: u32|
-                  -> Pixel<T> {
-                ContinuousColorScale::pixel_color_gen(
-                    iters_to_escape,
-                    ending_point,
-                    num_iterations,
-                    hue,
-                    sat,
-                    val,
-                    10.0,
-                )
+        let black = Pixel::from_hsb(hue, 0.0, 0.0);
+
+        match self {
+            InteriorColoring::Black => black,
+            InteriorColoring::Distance => {
+                match orbit.interior_distance.map(|d| d / info.pixel_size) {
+                    // (This also leaves out NaN)
+                    Some(pixels) if pixels >= 0.0 => {
+                        Pixel::from_hsb(hue, 0.6, 1.0 - (-pixels / 16.0).exp())
+                    }
+                    _ => black,
+                }
+            }
+            InteriorColoring::Magnitude => {
+                let magnitude = orbit.z.abs();
+                if !magnitude.is_finite() {
+                    return black;
+                }
+
+                let hue = (hue + 180.0 * magnitude).rem_euclid(360.0);
+                Pixel::from_hsb(hue, 0.8, 1.0)
+            }
+            InteriorColoring::Period => match orbit.period {
+                // (Steps of the golden angle keep neighbouring periods apart)
+                Some(period) => {
+                    let hue = (hue + 137.5 * (period - 1) as f64).rem_euclid(360.0);
+                    Pixel::from_hsb(hue, 0.8, 1.0)
+                }
+                None => black,
             },
+            InteriorColoring::MultiplierPhase => match orbit.multiplier {
+                Some(multiplier) if multiplier.abs().is_finite() => {
+                    let hue = (hue + multiplier.arg().to_degrees()).rem_euclid(360.0);
+                    let brightness = 1.0 - multiplier.abs().min(1.0) / 2.0;
+                    Pixel::from_hsb(hue, 0.8, brightness)
+                }
+                _ => black,
+            },
+        }
+    }
+
+    /// Makes a color scale that colors points inside the set (ones with
+    /// `info.max_iterations` iterations) this way and leaves everything else
+    /// to `exterior`
+    ///
+    /// Fails if the hue isn't finite.
+    pub fn with_exterior<S>(self, hue: f64, exterior: S) -> Result<InteriorColorScale<S>> {
+        check_hsb(hue, 1.0, 1.0)?;
+
+        Ok(InteriorColorScale {
+            coloring: self,
+            hue,
+            exterior,
+        })
+    }
+}
+
+/// A color scale that colors the inside of the set with an
+/// `InteriorColoring` and the outside with another color scale (see
+/// `InteriorColoring::with_exterior`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteriorColorScale<S> {
+    coloring: InteriorColoring,
+    hue: f64,
+    exterior: S,
+}
+
+impl<T, S> ColorScale<T> for InteriorColorScale<S>
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+    S: ColorScale<T>,
+    f64: From<T> + AsPrimitive<T>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        if orbit.iterations == info.max_iterations {
+            self.coloring
+                .pixel_color(orbit, info, self.hue)
+                .expect("the hue was checked when the color scale was made")
+        } else {
+            self.exterior.pixel_color(orbit, info)
+        }
+    }
+}
+
+/// What the thresholds of a `DiscreteColorScale`'s bands are measured in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BandThresholds {
+    /// The fraction of `info.max_iterations` a point went through (from 0
+    /// to 1)
+    Ratio,
+    /// The number of iterations a point went through
+    Iterations,
+    /// The percentage of the points that escaped that took no more
+    /// iterations than a point did (from 0 to 100), which needs
+    /// `MandelbrotConfig::histogram` on
+    ///
+    /// A point goes in the first band whose threshold it doesn't go past, so
+    /// the points that took the most iterations (at 100) land in a last band
+    /// ending at 100, and only the points in the set get the `rest` color.
+    /// Without a histogram (say, while recoloring a render made without one)
+    /// the ratio, as a percentage, is used instead rather than giving up on
+    /// coloring altogether.
+    Percentile,
+}
+
+/// Colors points in flat bands: each band is a threshold and a color, and a
+/// point gets the color of the first band whose threshold it's below (or,
+/// for percentiles, doesn't go past), or the `rest` color if there isn't one
+///
+/// The default bands go from red through yellow, green, white and magenta to
+/// white by ratio, with black for the rest (which covers the set).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteColorScale<T: Unsigned + Bounded> {
+    thresholds: BandThresholds,
+    bands: Vec<(f64, Pixel<T>)>,
+    rest: Pixel<T>,
+}
+
+impl<T> DiscreteColorScale<T>
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+{
+    /// Makes a color scale with the bands given, which have to be sorted by
+    /// threshold with no two bands sharing a threshold (a band that starts
+    /// where another one ends would never be used)
+    ///
+    /// Fails if the bands aren't sorted or overlap, or if any threshold is
+    /// negative, NaN or out of range (ratios go up to 1 and percentiles go up
+    /// to 100).
+    pub fn new(
+        thresholds: BandThresholds,
+        bands: Vec<(f64, Pixel<T>)>,
+        rest: Pixel<T>,
+    ) -> Result<DiscreteColorScale<T>> {
+        let max = match thresholds {
+            BandThresholds::Ratio => 1.0,
+            BandThresholds::Iterations => f64::INFINITY,
+            BandThresholds::Percentile => 100.0,
+        };
+
+        if bands
+            .iter()
+            .any(|(threshold, _)| !(0.0..=max).contains(threshold))
+        {
+            return Err(Error::InvalidConfig(
+                "band thresholds have to be in range (and can't be negative or NaN)",
+            ));
+        }
+
+        if bands.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(Error::InvalidConfig(
+                "bands have to be sorted by threshold and can't overlap",
+            ));
+        }
+
+        Ok(DiscreteColorScale {
+            thresholds,
+            bands,
+            rest,
+        })
+    }
+
+    /// The bands, sorted by threshold
+    pub fn bands(&self) -> &[(f64, Pixel<T>)] {
+        &self.bands
+    }
+}
+
+impl<T> Default for DiscreteColorScale<T>
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+{
+    fn default() -> DiscreteColorScale<T> {
+        let (min, max) = (T::min_value(), T::max_value());
+
+        DiscreteColorScale::new(
+            BandThresholds::Ratio,
+            vec![
+                (0.15, Pixel::new(max, min, min)),
+                (0.30, Pixel::new(max, max, min)),
+                (0.45, Pixel::new(min, max, min)),
+                (0.60, Pixel::new(max, max, max)),
+                (0.80, Pixel::new(max, min, max)),
+                (0.95, Pixel::new(max, max, max)),
+            ],
+            Pixel::new(min, min, min),
         )
+        .expect("the default bands are valid")
     }
 }
 
-pub struct DiscreteColorScale {}
-
-impl ColorScale for DiscreteColorScale {
-    fn pixel_color<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
-        iters_to_escape: u32,
-        _ending_point: ComplexNumber<f64>,
-        max_iterations: u32,
-    ) -> Pixel<T> {
-        match f64::from(iters_to_escape) / f64::from(max_iterations) {
-            p if p < 0.15 => Pixel::new(T::max_value(), T::min_value(), T::min_value()),
-            p if p < 0.30 => Pixel::new(T::max_value(), T::max_value(), T::min_value()),
-            p if p < 0.45 => Pixel::new(T::min_value(), T::max_value(), T::min_value()),
-            p if p < 0.60 => Pixel::new(T::max_value(), T::max_value(), T::max_value()),
-            p if p < 0.75 => Pixel::new(T::max_value(), T::min_value(), T::max_value()),
-            p if p < 0.80 => Pixel::new(T::max_value(), T::min_value(), T::max_value()),
-            p if p < 0.95 => Pixel::new(T::max_value(), T::max_value(), T::max_value()),
-            _ => Pixel::new(T::min_value(), T::min_value(), T::min_value()),
+impl<T> ColorScale<T> for DiscreteColorScale<T>
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        let ratio = f64::from(orbit.iterations) / f64::from(info.max_iterations);
+        let below = |value: f64| {
+            self.bands
+                .partition_point(|(threshold, _)| *threshold <= value)
+        };
+        let within = |value: f64| {
+            self.bands
+                .partition_point(|(threshold, _)| *threshold < value)
+        };
+
+        let band = match (self.thresholds, info.histogram) {
+            (BandThresholds::Ratio, _) => below(ratio),
+            (BandThresholds::Iterations, _) => below(f64::from(orbit.iterations)),
+            // The set isn't ranked, and the points that escaped run all the
+            // way up to 100:
+            (BandThresholds::Percentile, _) if orbit.iterations >= info.max_iterations => {
+                self.bands.len()
+            }
+            (BandThresholds::Percentile, Some(histogram)) => {
+                within(100.0 * histogram.rank_of(orbit))
+            }
+            (BandThresholds::Percentile, None) => within(100.0 * ratio),
+        };
+
+        match self.bands.get(band) {
+            Some((_, color)) => color.clone(),
+            None => self.rest.clone(),
         }
     }
 }
 
 pub struct SimpleColorScale {}
 
-impl ColorScale for SimpleColorScale {
-    fn pixel_color<T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>>(
-        iters_to_escape: u32,
-        _ending_point: ComplexNumber<f64>,
-        max_iterations: u32,
-    ) -> Pixel<T> {
-        if iters_to_escape == max_iterations {
+impl<T> ColorScale<T> for SimpleColorScale
+where
+    T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+{
+    fn pixel_color(&self, orbit: &Orbit, info: &RenderInfo) -> Pixel<T> {
+        if orbit.iterations == info.max_iterations {
             Pixel::new(T::max_value(), T::min_value(), T::min_value())
         } else {
             Pixel::new(T::min_value(), T::min_value(), T::min_value())
@@ -1,16 +1,24 @@
 //! A Complex Number Type
 //!
-//! This is only meant for use in the `mandelbrot` crate so it doesn't
-//! implement all the functionality possible for complex numbers, but it's
-//! pretty full featured.
+//! This is only meant for use in the `mandelbrot` crate, but it has what
+//! formulas need: arithmetic (with both `ComplexNumber`s and real numbers, and
+//! the assignment versions of the operators), conjugates, polar form, and the
+//! exponential, logarithm, power, square root and trig functions (all using
+//! the principal branch where there's a choice).
+//!
+//! `ComplexNumber`s can also be written out and parsed back (see the `Display`
+//! and `FromStr` impls).
 
 extern crate num_traits;
 use self::num_traits::{Float, Num};
+use error::{Error, Result};
 use std::cmp::Ordering;
 
 use std::{
     cmp::PartialOrd,
-    ops::{Add, Div, Mul, Sub},
+    fmt,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    str::FromStr,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -28,9 +36,163 @@ impl<T: Float> ComplexNumber<T> {
         ComplexNumber { r, i }
     }
 
+    /// The real part of the ComplexNumber
+    pub fn real(self) -> T {
+        self.r
+    }
+
+    /// The imaginary part of the ComplexNumber
+    pub fn imag(self) -> T {
+        self.i
+    }
+
     /// Find the absolute value of the ComplexNumber
     pub fn abs(self) -> T {
-        ((self.r * self.r) + (self.i * self.i)).sqrt()
+        self.norm_sqr().sqrt()
+    }
+
+    /// Find the square of the absolute value of the ComplexNumber
+    ///
+    /// This is cheaper than `abs` since there's no square root involved.
+    pub fn norm_sqr(self) -> T {
+        (self.r * self.r) + (self.i * self.i)
+    }
+
+    /// The complex conjugate (the imaginary part negated)
+    pub fn conj(self) -> ComplexNumber<T> {
+        ComplexNumber::new(self.r, -self.i)
+    }
+
+    /// The angle of the ComplexNumber from the positive real axis, in
+    /// (-π, π]
+    pub fn arg(self) -> T {
+        self.i.atan2(self.r)
+    }
+
+    /// Makes a ComplexNumber from its magnitude and angle
+    pub fn from_polar(r: T, theta: T) -> ComplexNumber<T> {
+        ComplexNumber::new(r * theta.cos(), r * theta.sin())
+    }
+
+    /// The magnitude and angle of the ComplexNumber (see `abs` and `arg`)
+    pub fn to_polar(self) -> (T, T) {
+        (self.abs(), self.arg())
+    }
+
+    /// `1 / self`
+    pub fn recip(self) -> ComplexNumber<T> {
+        let d = self.norm_sqr();
+        ComplexNumber::new(self.r / d, -self.i / d)
+    }
+
+    /// `e^self`
+    pub fn exp(self) -> ComplexNumber<T> {
+        ComplexNumber::from_polar(self.r.exp(), self.i)
+    }
+
+    /// The natural logarithm (the imaginary part is in (-π, π])
+    pub fn ln(self) -> ComplexNumber<T> {
+        ComplexNumber::new(self.abs().ln(), self.arg())
+    }
+
+    /// `self^n`, by repeated squaring
+    pub fn powi(self, n: i32) -> ComplexNumber<T> {
+        let mut result = ComplexNumber::new(T::one(), T::zero());
+        let (mut base, mut power) = (self, n.unsigned_abs());
+
+        while power > 0 {
+            if power & 1 == 1 {
+                result *= base;
+            }
+            base = base * base;
+            power >>= 1;
+        }
+
+        if n < 0 {
+            result.recip()
+        } else {
+            result
+        }
+    }
+
+    /// `self^x` for a real `x`
+    ///
+    /// `0^x` is 0 (or 1 when `x` is 0).
+    pub fn powf(self, x: T) -> ComplexNumber<T> {
+        if self.is_zero() {
+            return Self::zero_power(x.is_zero());
+        }
+
+        ComplexNumber::from_polar(self.abs().powf(x), self.arg() * x)
+    }
+
+    /// `self^w`, which is `e^(w ln(self))`
+    ///
+    /// `0^w` is 0 (or 1 when `w` is 0).
+    pub fn powc(self, w: ComplexNumber<T>) -> ComplexNumber<T> {
+        if self.is_zero() {
+            return Self::zero_power(w.is_zero());
+        }
+
+        (w * self.ln()).exp()
+    }
+
+    /// The square root (with a non-negative real part)
+    pub fn sqrt(self) -> ComplexNumber<T> {
+        let two = T::one() + T::one();
+        let r = self.abs();
+
+        let re = ((r + self.r) / two).sqrt();
+        let im = ((r - self.r) / two).sqrt();
+
+        ComplexNumber::new(re, if self.i < T::zero() { -im } else { im })
+    }
+
+    /// The sine
+    pub fn sin(self) -> ComplexNumber<T> {
+        ComplexNumber::new(self.r.sin() * self.i.cosh(), self.r.cos() * self.i.sinh())
+    }
+
+    /// The cosine
+    pub fn cos(self) -> ComplexNumber<T> {
+        ComplexNumber::new(self.r.cos() * self.i.cosh(), -self.r.sin() * self.i.sinh())
+    }
+
+    /// The tangent
+    pub fn tan(self) -> ComplexNumber<T> {
+        self.sin() / self.cos()
+    }
+
+    /// The hyperbolic sine
+    pub fn sinh(self) -> ComplexNumber<T> {
+        ComplexNumber::new(self.r.sinh() * self.i.cos(), self.r.cosh() * self.i.sin())
+    }
+
+    /// The hyperbolic cosine
+    pub fn cosh(self) -> ComplexNumber<T> {
+        ComplexNumber::new(self.r.cosh() * self.i.cos(), self.r.sinh() * self.i.sin())
+    }
+
+    /// The hyperbolic tangent
+    pub fn tanh(self) -> ComplexNumber<T> {
+        self.sinh() / self.cosh()
+    }
+
+    /// Whether both parts are finite
+    pub fn is_finite(self) -> bool {
+        self.r.is_finite() && self.i.is_finite()
+    }
+
+    fn is_zero(self) -> bool {
+        self.r.is_zero() && self.i.is_zero()
+    }
+
+    fn zero_power(zero_exponent: bool) -> ComplexNumber<T> {
+        if zero_exponent {
+            ComplexNumber::new(T::one(), T::zero())
+        } else {
+            ComplexNumber::new(T::zero(), T::zero())
+        }
     }
 }
 
@@ -58,6 +220,56 @@ impl<T: Add<Output = T> + Float, R: Num + Into<T> + Copy> Add<R> for ComplexNumb
     }
 }
 
+impl<T: Sub<Output = T> + Float> Sub<ComplexNumber<T>> for ComplexNumber<T> {
+    type Output = ComplexNumber<T>;
+
+    /// Subtracts another `ComplexNumber` from our `ComplexNumber`
+    fn sub(self, other: ComplexNumber<T>) -> ComplexNumber<T> {
+        ComplexNumber {
+            r: self.r - other.r,
+            i: self.i - other.i,
+        }
+    }
+}
+
+impl<T: Sub<Output = T> + Float, R: Num + Into<T> + Copy> Sub<R> for ComplexNumber<T> {
+    type Output = ComplexNumber<T>;
+
+    /// Subtracts something that _isn't_ a `ComplexNumber` from our
+    /// `ComplexNumber`
+    fn sub(self, other: R) -> ComplexNumber<T> {
+        ComplexNumber {
+            r: self.r - (other.into()),
+            i: self.i,
+        }
+    }
+}
+
+impl<T: Float> Neg for ComplexNumber<T> {
+    type Output = ComplexNumber<T>;
+
+    fn neg(self) -> ComplexNumber<T> {
+        ComplexNumber {
+            r: -self.r,
+            i: -self.i,
+        }
+    }
+}
+
+impl<T: Float> Div<ComplexNumber<T>> for ComplexNumber<T> {
+    type Output = ComplexNumber<T>;
+
+    /// Divides our `ComplexNumber` by another `ComplexNumber`
+    fn div(self, other: ComplexNumber<T>) -> ComplexNumber<T> {
+        let d = other.norm_sqr();
+
+        ComplexNumber {
+            r: ((self.r * other.r) + (self.i * other.i)) / d,
+            i: ((self.i * other.r) - (self.r * other.i)) / d,
+        }
+    }
+}
+
 impl<T: Div<Output = T> + Float, R: Num + Into<T> + Copy> Div<R> for ComplexNumber<T> {
     type Output = ComplexNumber<T>;
 
@@ -94,6 +306,120 @@ impl<T: Mul<Output = T> + Float, R: Num + Into<T> + Copy> Mul<R> for ComplexNumb
     }
 }
 
+/// Implements an assignment operator (for both `ComplexNumber`s and things
+/// that _aren't_ `ComplexNumber`s) in terms of the regular operator
+macro_rules! assign_op {
+    ($trait:ident, $method:ident, $op:ident, $op_method:ident) => {
+        impl<T: Float> $trait<ComplexNumber<T>> for ComplexNumber<T> {
+            fn $method(&mut self, other: ComplexNumber<T>) {
+                *self = $op::$op_method(*self, other);
+            }
+        }
+
+        impl<T: Float, R: Num + Into<T> + Copy> $trait<R> for ComplexNumber<T> {
+            fn $method(&mut self, other: R) {
+                *self = $op::$op_method(*self, other);
+            }
+        }
+    };
+}
+
+assign_op!(AddAssign, add_assign, Add, add);
+assign_op!(SubAssign, sub_assign, Sub, sub);
+assign_op!(MulAssign, mul_assign, Mul, mul);
+assign_op!(DivAssign, div_assign, Div, div);
+
+impl<T: Float + fmt::Display> fmt::Display for ComplexNumber<T> {
+    /// Writes the number as `a+bi` (or `a-bi`); a precision, if one is given,
+    /// applies to both parts
+    ///
+    /// Without a precision, `f64`s are written with just enough digits to be
+    /// parsed back to exactly the same value.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (sign, imag) = if self.i.is_sign_negative() {
+            ('-', -self.i)
+        } else {
+            ('+', self.i)
+        };
+
+        match f.precision() {
+            Some(p) => write!(f, "{:.*}{}{:.*}i", p, self.r, sign, p, imag),
+            None => write!(f, "{}{}{}i", self.r, sign, imag),
+        }
+    }
+}
+
+impl<T: Float + FromStr> FromStr for ComplexNumber<T> {
+    type Err = Error;
+
+    /// Parses a complex number written in any of these ways:
+    ///   - `a+bi`, `a-bi`, `a`, `bi` or `i` (`j` works in place of `i`, and
+    ///     there can be spaces around the sign)
+    ///   - `(a, b)`
+    ///   - `r∠θ` (polar form, with `θ` in radians, or in degrees if it ends
+    ///     with `°`)
+    fn from_str(s: &str) -> Result<ComplexNumber<T>> {
+        let error = || Error::Parse {
+            input: s.to_string(),
+            expected: "a complex number",
+        };
+        let number = |s: &str| s.trim().parse::<T>().map_err(|_| error());
+
+        let s = s.trim();
+
+        if s.starts_with('(') && s.ends_with(')') {
+            let mut parts = s[1..s.len() - 1].split(',');
+
+            return match (parts.next(), parts.next(), parts.next()) {
+                (Some(r), Some(i), None) => Ok(ComplexNumber::new(number(r)?, number(i)?)),
+                _ => Err(error()),
+            };
+        }
+
+        if let Some(angle) = s.find('∠') {
+            let (r, theta) = (&s[..angle], &s[angle + '∠'.len_utf8()..]);
+            let theta = match theta.trim().strip_suffix('°') {
+                Some(degrees) => number(degrees)?.to_radians(),
+                None => number(theta)?,
+            };
+
+            return Ok(ComplexNumber::from_polar(number(r)?, theta));
+        }
+
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let imaginary = match s.strip_suffix('i').or_else(|| s.strip_suffix('j')) {
+            Some(imaginary) => imaginary,
+            None => return Ok(ComplexNumber::new(number(&s)?, T::zero())),
+        };
+
+        // The imaginary part starts at the last sign that isn't the first
+        // character or part of an exponent:
+        let bytes = imaginary.as_bytes();
+        let split = (1..bytes.len())
+            .rev()
+            .find(|&k| {
+                (bytes[k] == b'+' || bytes[k] == b'-')
+                    && bytes[k - 1] != b'e'
+                    && bytes[k - 1] != b'E'
+            })
+            .unwrap_or(0);
+
+        let (real, imaginary) = imaginary.split_at(split);
+        let real = if real.is_empty() {
+            T::zero()
+        } else {
+            number(real)?
+        };
+        let imaginary = match imaginary {
+            "" | "+" => T::one(),
+            "-" => -T::one(),
+            imaginary => number(imaginary)?,
+        };
+
+        Ok(ComplexNumber::new(real, imaginary))
+    }
+}
+
 impl<T: PartialEq<T> + Float, J: Into<T> + Float> PartialEq<ComplexNumber<J>> for ComplexNumber<T> {
     fn eq(&self, other: &ComplexNumber<J>) -> bool {
         (self.r == other.r.into()) && (self.i == other.i.into())
@@ -112,6 +438,21 @@ impl<T: PartialOrd<T> + Float, J: Into<T> + Float> PartialOrd<ComplexNumber<J>>
 mod tests {
 
     use complex_number::ComplexNumber;
+    use std::f64::consts::{E, FRAC_PI_2, PI};
+
+    fn points() -> Vec<ComplexNumber<f64>> {
+        vec![
+            ComplexNumber::new(1.0, 0.0),
+            ComplexNumber::new(0.5, -0.25),
+            ComplexNumber::new(-1.5, 2.0),
+            ComplexNumber::new(-3.0, -0.5),
+            ComplexNumber::new(0.0, 0.7),
+        ]
+    }
+
+    fn close(a: ComplexNumber<f64>, b: ComplexNumber<f64>) -> bool {
+        (a - b).abs() <= 1e-12 * (1.0 + b.abs())
+    }
 
     #[test]
     fn complex_addition() {
@@ -139,6 +480,16 @@ mod tests {
         assert_eq!(c.r, 0.5);
     }
 
+    #[test]
+    fn complex_subtraction() {
+        let a = ComplexNumber::new(4.0, 5.0);
+        let b = ComplexNumber::new(5.5, 9.0);
+
+        let c = a - b;
+        assert_eq!(c.r, -1.5);
+        assert_eq!(c.i, -4.0);
+    }
+
     #[test]
     fn complex_multiplication() {
         let a = ComplexNumber::new(1.0, 1.0);
@@ -179,6 +530,13 @@ mod tests {
         assert_eq!(ComplexNumber::new(0.0, 5.0).abs(), 5.0);
     }
 
+    #[test]
+    fn complex_norm_sqr() {
+        assert_eq!(ComplexNumber::new(3.0, 4.0).norm_sqr(), 25.0);
+        assert_eq!(ComplexNumber::new(-3.0, -4.0).norm_sqr(), 25.0);
+        assert_eq!(ComplexNumber::new(0.0, 0.0).norm_sqr(), 0.0);
+    }
+
     #[test]
     fn complex_eq() {
         let a = ComplexNumber::new(2.0, 2.0);
@@ -199,4 +557,232 @@ mod tests {
         assert!(a > ComplexNumber::new(2.0, 0.0));
         assert!(a == ComplexNumber::new(2.0, 2.0));
     }
+
+    #[test]
+    fn complex_subtraction2() {
+        let c = ComplexNumber::new(4.0, 5.0) - 1.5;
+        assert_eq!(c, ComplexNumber::new(2.5, 5.0));
+
+        assert_eq!(-c, ComplexNumber::new(-2.5, -5.0));
+    }
+
+    #[test]
+    fn complex_division2() {
+        // (1 + 2i)(3 - i) = 5 + 5i
+        let a = ComplexNumber::new(5.0, 5.0);
+        let b = ComplexNumber::new(3.0, -1.0);
+
+        assert_eq!(a / b, ComplexNumber::new(1.0, 2.0));
+        assert_eq!(b.recip(), ComplexNumber::new(0.3, 0.1));
+
+        for a in points() {
+            for b in points() {
+                assert!(close((a / b) * b, a));
+            }
+        }
+    }
+
+    #[test]
+    fn complex_assign_ops() {
+        let mut a = ComplexNumber::new(1.0, 2.0);
+
+        a += ComplexNumber::new(1.0, 1.0);
+        assert_eq!(a, ComplexNumber::new(2.0, 3.0));
+        a -= 1.0;
+        assert_eq!(a, ComplexNumber::new(1.0, 3.0));
+        a *= ComplexNumber::new(0.0, 1.0);
+        assert_eq!(a, ComplexNumber::new(-3.0, 1.0));
+        a /= 2.0;
+        assert_eq!(a, ComplexNumber::new(-1.5, 0.5));
+        a *= 2;
+        a -= ComplexNumber::new(-3.0, 1.0);
+        assert_eq!(a, ComplexNumber::new(0.0, 0.0));
+        a += 4.0;
+        a /= ComplexNumber::new(0.0, 2.0);
+        assert_eq!(a, ComplexNumber::new(0.0, -2.0));
+    }
+
+    #[test]
+    fn complex_conj_and_polar() {
+        for z in points() {
+            assert_eq!(z.conj().conj(), z);
+            assert_eq!(z * z.conj(), ComplexNumber::new(z.norm_sqr(), 0.0));
+
+            let (r, theta) = z.to_polar();
+            assert!(close(ComplexNumber::from_polar(r, theta), z));
+        }
+
+        assert_eq!(ComplexNumber::new(0.0, 1.0).arg(), FRAC_PI_2);
+        assert_eq!(ComplexNumber::new(-1.0, 0.0).arg(), PI);
+        assert_eq!(ComplexNumber::new(2.0, 0.0).to_polar(), (2.0, 0.0));
+    }
+
+    #[test]
+    fn complex_exp_and_ln() {
+        let i = ComplexNumber::new(0.0, 1.0);
+
+        // e^(iπ) = -1
+        assert!(close((i * PI).exp(), ComplexNumber::new(-1.0, 0.0)));
+        assert!(close(
+            ComplexNumber::new(1.0, 0.0).exp(),
+            ComplexNumber::new(E, 0.0)
+        ));
+        assert!(close(ComplexNumber::new(-1.0, 0.0).ln(), i * PI));
+
+        for a in points() {
+            assert!(close(a.ln().exp(), a));
+            assert!(close(a.exp().ln(), a));
+
+            for b in points() {
+                assert!(close((a + b).exp(), a.exp() * b.exp()));
+            }
+        }
+    }
+
+    #[test]
+    fn complex_powers() {
+        let i = ComplexNumber::new(0.0, 1.0);
+        let zero = ComplexNumber::new(0.0, 0.0);
+
+        assert_eq!(i.powi(2), ComplexNumber::new(-1.0, 0.0));
+        assert_eq!(i.powi(-1), ComplexNumber::new(0.0, -1.0));
+        // i^i = e^(-π/2)
+        assert!(close(
+            i.powc(i),
+            ComplexNumber::new((-FRAC_PI_2).exp(), 0.0)
+        ));
+
+        assert_eq!(zero.powf(2.5), zero);
+        assert_eq!(zero.powc(i), zero);
+        assert_eq!(zero.powf(0.0), ComplexNumber::new(1.0, 0.0));
+
+        for z in points() {
+            assert_eq!(z.powi(0), ComplexNumber::new(1.0, 0.0));
+            assert!(close(z.powi(3), z * z * z));
+            assert!(close(z.powi(-2) * z * z, ComplexNumber::new(1.0, 0.0)));
+            assert!(close(z.powf(3.0), z.powi(3)));
+            assert!(close(z.powc(ComplexNumber::new(2.0, 0.0)), z * z));
+            assert!(close(z.powf(0.5), z.sqrt()));
+
+            let root = z.sqrt();
+            assert!(close(root * root, z));
+            assert!(root.real() >= 0.0);
+        }
+
+        assert_eq!(
+            ComplexNumber::new(-4.0, 0.0).sqrt(),
+            ComplexNumber::new(0.0, 2.0)
+        );
+        assert_eq!(
+            ComplexNumber::new(0.0, -2.0).sqrt(),
+            ComplexNumber::new(1.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn complex_trig() {
+        let i = ComplexNumber::new(0.0, 1.0);
+        let one = ComplexNumber::new(1.0, 0.0);
+
+        for z in points() {
+            assert!(close(z.sin() * z.sin() + z.cos() * z.cos(), one));
+            assert!(close(z.cosh() * z.cosh() - z.sinh() * z.sinh(), one));
+            assert!(close(z.tan(), z.sin() / z.cos()));
+            assert!(close(z.tanh(), z.sinh() / z.cosh()));
+
+            // sin(iz) = i sinh(z) and cos(iz) = cosh(z)
+            assert!(close((i * z).sin(), i * z.sinh()));
+            assert!(close((i * z).cos(), z.cosh()));
+
+            // Euler's formula: e^(iz) = cos(z) + i sin(z)
+            assert!(close((i * z).exp(), z.cos() + i * z.sin()));
+        }
+
+        assert!(ComplexNumber::new(1.0, 2.0).is_finite());
+        assert!(!ComplexNumber::new(1.0, f64::INFINITY).is_finite());
+    }
+
+    #[test]
+    fn complex_display() {
+        assert_eq!(ComplexNumber::new(1.5, -2.0).to_string(), "1.5-2i");
+        assert_eq!(ComplexNumber::new(-0.25, 3.0).to_string(), "-0.25+3i");
+        assert_eq!(ComplexNumber::new(0.0, -0.0).to_string(), "0-0i");
+        assert_eq!(format!("{:.3}", ComplexNumber::new(PI, -E)), "3.142-2.718i");
+    }
+
+    #[test]
+    fn complex_parse() {
+        let parse = |s: &str| s.parse::<ComplexNumber<f64>>().unwrap();
+
+        assert_eq!(
+            parse("-0.743643887+0.131825904i"),
+            ComplexNumber::new(-0.743643887, 0.131825904)
+        );
+        assert_eq!(parse(" 1.5 - 2i "), ComplexNumber::new(1.5, -2.0));
+        assert_eq!(parse("1e-3+2E+2j"), ComplexNumber::new(1e-3, 200.0));
+        assert_eq!(parse("-1e-3"), ComplexNumber::new(-1e-3, 0.0));
+        assert_eq!(parse("2.5i"), ComplexNumber::new(0.0, 2.5));
+        assert_eq!(parse("-i"), ComplexNumber::new(0.0, -1.0));
+        assert_eq!(parse("3+i"), ComplexNumber::new(3.0, 1.0));
+        assert_eq!(parse("(0.5, -4)"), ComplexNumber::new(0.5, -4.0));
+        assert_eq!(parse("2∠0"), ComplexNumber::new(2.0, 0.0));
+        assert!(close(parse("2∠90°"), ComplexNumber::new(0.0, 2.0)));
+        assert!(close(
+            parse("1 ∠ 3.141592653589793"),
+            ComplexNumber::new(-1.0, 0.0)
+        ));
+
+        for bad in [
+            "",
+            "i2",
+            "1+2",
+            "(1, 2, 3)",
+            "(1 2)",
+            "1+2k",
+            "2∠",
+            "abc",
+            "1++2i",
+        ]
+        .iter()
+        {
+            assert!(bad.parse::<ComplexNumber<f64>>().is_err(), "{}", bad);
+        }
+
+        assert_eq!(
+            "1+xi"
+                .parse::<ComplexNumber<f64>>()
+                .unwrap_err()
+                .to_string(),
+            "Couldn't parse `1+xi` as a complex number"
+        );
+    }
+
+    #[test]
+    fn complex_round_trip() {
+        let values = [
+            0.0,
+            -0.0,
+            1.0 / 3.0,
+            -0.743643887037158,
+            1e-300,
+            5e-324,
+            -1.7976931348623157e308,
+            6.02214076e23,
+            f64::INFINITY,
+        ];
+
+        for &r in values.iter() {
+            for &i in values.iter() {
+                let z = ComplexNumber::new(r, i);
+                let parsed: ComplexNumber<f64> = z.to_string().parse().unwrap();
+
+                assert_eq!(parsed.real().to_bits(), r.to_bits(), "{}", z);
+                assert_eq!(parsed.imag().to_bits(), i.to_bits(), "{}", z);
+            }
+        }
+
+        let nan = ComplexNumber::new(f64::NAN, f64::NAN).to_string();
+        let nan: ComplexNumber<f64> = nan.parse().unwrap();
+        assert!(nan.real().is_nan() && nan.imag().is_nan());
+    }
 }
@@ -0,0 +1,91 @@
+//! The Error Type
+//!
+//! Everything in this library that can be handed bad input reports it with
+//! an `Error` instead of panicking.
+
+use std::{error, fmt, result};
+
+/// The things that can go wrong when setting up a render
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A color given as hue/saturation/brightness that's out of range: the
+    /// hue has to be finite and the saturation and brightness have to be
+    /// between 0 and 1
+    InvalidColor {
+        hue: f64,
+        saturation: f64,
+        brightness: f64,
+    },
+    /// A color given in some other color space (HSL, Lab, OKLab, ...) with
+    /// components that are out of range
+    InvalidColorComponents(&'static str),
+    /// Dimensions with no pixels in them
+    ZeroDimensions { width: u32, height: u32 },
+    /// Dimensions with too many pixels in them to allocate
+    DimensionsTooLarge { width: u32, height: u32 },
+    /// A viewport that doesn't cover any area or isn't finite
+    InvalidViewport(&'static str),
+    /// Settings that don't work together
+    InvalidConfig(&'static str),
+    /// Gradient stops that are missing, out of order or out of range
+    InvalidGradient(&'static str),
+    /// A palette file that doesn't parse; `line` counts from 1
+    InvalidPalette { line: usize, reason: &'static str },
+    /// A file that couldn't be read
+    Io { path: String, reason: String },
+    /// A formula expression that doesn't parse; `column` counts characters
+    /// from 1
+    InvalidExpression { column: usize, reason: &'static str },
+    /// Text that doesn't parse as the thing that was `expected` (a complex
+    /// number, a viewport)
+    Parse {
+        input: String,
+        expected: &'static str,
+    },
+}
+
+/// A `Result` with this library's `Error`
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidColor {
+                hue,
+                saturation,
+                brightness,
+            } => write!(
+                f,
+                "Invalid HSB values: {} {} {} (the hue must be finite and the saturation \
+                 and brightness must be between 0 and 1)",
+                hue, saturation, brightness
+            ),
+            Error::InvalidColorComponents(reason) => write!(f, "Invalid color: {}", reason),
+            Error::ZeroDimensions { width, height } => {
+                write!(f, "Invalid dimensions: {}x{} has no pixels", width, height)
+            }
+            Error::DimensionsTooLarge { width, height } => {
+                write!(f, "Invalid dimensions: {}x{} is too big", width, height)
+            }
+            Error::InvalidViewport(reason) => write!(f, "Invalid viewport: {}", reason),
+            Error::InvalidConfig(reason) => write!(f, "Invalid config: {}", reason),
+            Error::InvalidGradient(reason) => write!(f, "Invalid gradient: {}", reason),
+            Error::InvalidPalette { line, reason } => {
+                write!(f, "Invalid palette: {} (on line {})", reason, line)
+            }
+            Error::Io {
+                ref path,
+                ref reason,
+            } => write!(f, "Couldn't read `{}`: {}", path, reason),
+            Error::InvalidExpression { column, reason } => {
+                write!(f, "Invalid expression: {} (at column {})", reason, column)
+            }
+            Error::Parse {
+                ref input,
+                expected,
+            } => write!(f, "Couldn't parse `{}` as {}", input, expected),
+        }
+    }
+}
+
+impl error::Error for Error {}
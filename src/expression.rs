@@ -0,0 +1,710 @@
+//! User-Defined Formulas
+//!
+//! `Expression` lets formulas be typed in (`z^3 - z + c`, `sin(z) * c`, ...)
+//! instead of written in Rust. Expressions are parsed once, turned into a
+//! tree of closures (with any constant parts worked out ahead of time) and can
+//! then be used anywhere a `Formula` can.
+//!
+//! The language is small:
+//!   - the variables are `z` and `c`, and `i`, `pi` and `e` are constants
+//!   - numbers can be real (`2`, `0.5`, `1e-3`) or imaginary (`2i`, `0.5i`)
+//!   - the operators are `+`, `-`, `*`, `/` and `^` (which binds tightest and
+//!     groups right to left, so `-z^2` is `-(z^2)`)
+//!   - the functions are `sin`, `cos`, `tan`, `sinh`, `cosh`, `tanh`, `exp`,
+//!     `ln` (or `log`), `sqrt`, `abs`, `conj`, `re` and `im`; arguments go in
+//!     parentheses
+//!
+//! Parse errors say which column (counting characters from 1) the problem is
+//! at.
+
+use complex_number::ComplexNumber;
+use error::{Error, Result};
+use formula::Formula;
+use std::{f64::consts, fmt, str::FromStr};
+
+/// A compiled piece of an expression: takes `z` and `c` and gives back a value
+type Compiled =
+    Box<dyn Fn(ComplexNumber<f64>, ComplexNumber<f64>) -> ComplexNumber<f64> + Send + Sync>;
+
+/// A parsed and compiled formula
+///
+/// Use `Expression::parse` (or `str::parse`) to make one.
+pub struct Expression {
+    source: String,
+    compiled: Compiled,
+    degree: Option<f64>,
+}
+
+impl Expression {
+    /// Parses and compiles an expression
+    ///
+    /// Fails with `Error::InvalidExpression` (which has the column the problem
+    /// is at) if the expression doesn't parse.
+    pub fn parse(source: &str) -> Result<Expression> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            end: source.chars().count() + 1,
+        };
+
+        let node = parser.expression()?;
+
+        match parser.peek() {
+            Some(&Token {
+                kind: TokenKind::Close,
+                column,
+            }) => return Err(invalid(column, "unmatched `)`")),
+            Some(token) => return Err(invalid(token.column, "expected an operator")),
+            None => {}
+        }
+
+        Ok(Expression {
+            source: source.to_string(),
+            compiled: compile(&node),
+            degree: node.degree(),
+        })
+    }
+
+    /// The text this expression was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the expression for the `z` and `c` given
+    pub fn evaluate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        (self.compiled)(z, c)
+    }
+}
+
+impl Formula for Expression {
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        self.evaluate(z, c)
+    }
+
+    /// Besides getting past the escape radius, orbits that blow up (`1 / z`
+    /// or `ln(z)` at `z = 0`, say) into NaNs count as having escaped;
+    /// otherwise they'd never stop and end up looking like they're in the set.
+    fn escaped(&self, z: ComplexNumber<f64>, escape_radius_sqr: f64) -> bool {
+        let norm_sqr = z.norm_sqr();
+        norm_sqr.is_nan() || norm_sqr >= escape_radius_sqr
+    }
+
+    /// The degree of the expression in `z`, if it's a polynomial (or close
+    /// enough: `conj`, `abs`, `re` and `im` don't change it) of degree greater
+    /// than 1; otherwise 2 is as good a guess as any.
+    fn degree(&self) -> f64 {
+        match self.degree {
+            Some(degree) if degree > 1.0 => degree,
+            _ => 2.0,
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Expression> {
+        Expression::parse(source)
+    }
+}
+
+impl fmt::Debug for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Expression").field(&self.source).finish()
+    }
+}
+
+fn invalid(column: usize, reason: &'static str) -> Error {
+    Error::InvalidExpression { column, reason }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(ComplexNumber<f64>),
+    Name(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    /// Where the token starts (counting characters from 1)
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (start, ch) = (i, chars[i]);
+        let column = start + 1;
+
+        let kind = if ch.is_whitespace() {
+            i += 1;
+            continue;
+        } else if ch.is_ascii_digit() || ch == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+
+            // An exponent (only if there are digits after it, so that `2e`
+            // is an error rather than something surprising):
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    while j < chars.len() && chars[j].is_ascii_digit() {
+                        j += 1;
+                    }
+                    i = j;
+                }
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let value: f64 = text
+                .parse()
+                .map_err(|_| invalid(column, "invalid number"))?;
+
+            // A trailing `i` makes the number imaginary (unless it's the start
+            // of a name):
+            if i < chars.len()
+                && chars[i] == 'i'
+                && !matches!(chars.get(i + 1), Some(c) if c.is_alphanumeric())
+            {
+                i += 1;
+                TokenKind::Number(ComplexNumber::new(0.0, value))
+            } else {
+                TokenKind::Number(ComplexNumber::new(value, 0.0))
+            }
+        } else if ch.is_alphabetic() {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            TokenKind::Name(chars[start..i].iter().collect())
+        } else {
+            i += 1;
+
+            match ch {
+                '+' | '-' | '*' | '/' | '^' => TokenKind::Operator(ch),
+                '(' => TokenKind::Open,
+                ')' => TokenKind::Close,
+                _ => return Err(invalid(column, "unexpected character")),
+            }
+        };
+
+        tokens.push(Token { kind, column });
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Sqrt,
+    Abs,
+    Conj,
+    Re,
+    Im,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "ln" | "log" => Function::Ln,
+            "sqrt" => Function::Sqrt,
+            "abs" => Function::Abs,
+            "conj" => Function::Conj,
+            "re" => Function::Re,
+            "im" => Function::Im,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, z: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        match self {
+            Function::Sin => z.sin(),
+            Function::Cos => z.cos(),
+            Function::Tan => z.tan(),
+            Function::Sinh => z.sinh(),
+            Function::Cosh => z.cosh(),
+            Function::Tanh => z.tanh(),
+            Function::Exp => z.exp(),
+            Function::Ln => z.ln(),
+            Function::Sqrt => z.sqrt(),
+            Function::Abs => ComplexNumber::new(z.abs(), 0.0),
+            Function::Conj => z.conj(),
+            Function::Re => ComplexNumber::new(z.real(), 0.0),
+            Function::Im => ComplexNumber::new(z.imag(), 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(ComplexNumber<f64>),
+    Z,
+    C,
+    Negate(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+impl Node {
+    /// Whether the value of this node doesn't depend on `z` or `c`
+    fn is_constant(&self) -> bool {
+        match *self {
+            Node::Number(_) => true,
+            Node::Z | Node::C => false,
+            Node::Negate(ref a) | Node::Call(_, ref a) => a.is_constant(),
+            Node::Binary(_, ref a, ref b) => a.is_constant() && b.is_constant(),
+        }
+    }
+
+    /// The value of a real constant node
+    fn real_constant(&self) -> Option<f64> {
+        if !self.is_constant() {
+            return None;
+        }
+
+        let value = compile(self)(ComplexNumber::new(0.0, 0.0), ComplexNumber::new(0.0, 0.0));
+        if value.imag() == 0.0 {
+            Some(value.real())
+        } else {
+            None
+        }
+    }
+
+    /// The degree of this node in `z`, if it's (more or less) a polynomial
+    fn degree(&self) -> Option<f64> {
+        if self.is_constant() {
+            return Some(0.0);
+        }
+
+        match *self {
+            Node::Number(_) | Node::C => Some(0.0),
+            Node::Z => Some(1.0),
+            Node::Negate(ref a) => a.degree(),
+            Node::Call(function, ref a) => match function {
+                Function::Abs | Function::Conj | Function::Re | Function::Im => a.degree(),
+                _ => a.degree().filter(|d| *d == 0.0),
+            },
+            Node::Binary(op, ref a, ref b) => {
+                let (a, b_degree) = (a.degree()?, b.degree()?);
+
+                match op {
+                    '+' | '-' => Some(a.max(b_degree)),
+                    '*' => Some(a + b_degree),
+                    '/' if b_degree == 0.0 => Some(a),
+                    '^' => b.real_constant().filter(|n| *n >= 0.0).map(|n| a * n),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Turns a node into a closure; parts that don't depend on `z` or `c` are
+/// worked out up front
+fn compile(node: &Node) -> Compiled {
+    if let Node::Number(value) = *node {
+        return Box::new(move |_, _| value);
+    }
+
+    if node.is_constant() {
+        let value = compile_parts(node)(ComplexNumber::new(0.0, 0.0), ComplexNumber::new(0.0, 0.0));
+        return Box::new(move |_, _| value);
+    }
+
+    compile_parts(node)
+}
+
+fn compile_parts(node: &Node) -> Compiled {
+    match *node {
+        Node::Number(value) => Box::new(move |_, _| value),
+        Node::Z => Box::new(|z, _| z),
+        Node::C => Box::new(|_, c| c),
+        Node::Negate(ref a) => {
+            let a = compile(a);
+            Box::new(move |z, c| -a(z, c))
+        }
+        Node::Call(function, ref a) => {
+            let a = compile(a);
+            Box::new(move |z, c| function.apply(a(z, c)))
+        }
+        Node::Binary('^', ref a, ref b) => {
+            let power = b.real_constant();
+
+            match power {
+                Some(2.0) => {
+                    let a = compile(a);
+                    Box::new(move |z, c| {
+                        let a = a(z, c);
+                        a * a
+                    })
+                }
+                Some(n) if n.fract() == 0.0 && n.abs() <= f64::from(i32::MAX) => {
+                    let (a, n) = (compile(a), n as i32);
+                    Box::new(move |z, c| a(z, c).powi(n))
+                }
+                _ => {
+                    let (a, b) = (compile(a), compile(b));
+                    Box::new(move |z, c| a(z, c).powc(b(z, c)))
+                }
+            }
+        }
+        Node::Binary(op, ref a, ref b) => {
+            let (a, b) = (compile(a), compile(b));
+
+            match op {
+                '+' => Box::new(move |z, c| a(z, c) + b(z, c)),
+                '-' => Box::new(move |z, c| a(z, c) - b(z, c)),
+                '*' => Box::new(move |z, c| a(z, c) * b(z, c)),
+                _ => Box::new(move |z, c| a(z, c) / b(z, c)),
+            }
+        }
+    }
+}
+
+/// A recursive descent parser over the tokens
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// The column just past the end of the input (for errors about running
+    /// out of input)
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    /// Moves past the next token if it's the operator given
+    fn eat(&mut self, operators: &[char]) -> Option<char> {
+        match self.peek() {
+            Some(&Token {
+                kind: TokenKind::Operator(op),
+                ..
+            }) if operators.contains(&op) => {
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    /// `expression := term (('+' | '-') term)*`
+    fn expression(&mut self) -> Result<Node> {
+        let mut node = self.term()?;
+
+        while let Some(op) = self.eat(&['+', '-']) {
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+
+        Ok(node)
+    }
+
+    /// `term := unary (('*' | '/') unary)*`
+    fn term(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+
+        while let Some(op) = self.eat(&['*', '/']) {
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+
+        Ok(node)
+    }
+
+    /// `unary := ('-' | '+') unary | power`
+    fn unary(&mut self) -> Result<Node> {
+        match self.eat(&['-', '+']) {
+            Some('-') => Ok(Node::Negate(Box::new(self.unary()?))),
+            Some(_) => self.unary(),
+            None => self.power(),
+        }
+    }
+
+    /// `power := primary ('^' unary)?`
+    fn power(&mut self) -> Result<Node> {
+        let base = self.primary()?;
+
+        if self.eat(&['^']).is_some() {
+            Ok(Node::Binary('^', Box::new(base), Box::new(self.unary()?)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// `primary := number | variable | constant | function '(' expression ')'
+    /// | '(' expression ')'`
+    fn primary(&mut self) -> Result<Node> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(invalid(self.end, "unexpected end of expression")),
+        };
+
+        match token.kind {
+            TokenKind::Number(value) => Ok(Node::Number(value)),
+            TokenKind::Open => self.parenthesized(token.column),
+            TokenKind::Name(ref name) => match name.as_str() {
+                "z" => Ok(Node::Z),
+                "c" => Ok(Node::C),
+                "i" => Ok(Node::Number(ComplexNumber::new(0.0, 1.0))),
+                "pi" => Ok(Node::Number(ComplexNumber::new(consts::PI, 0.0))),
+                "e" => Ok(Node::Number(ComplexNumber::new(consts::E, 0.0))),
+                name => {
+                    let function = Function::from_name(name)
+                        .ok_or_else(|| invalid(token.column, "unknown variable or function"))?;
+
+                    match self.next() {
+                        Some(&Token {
+                            kind: TokenKind::Open,
+                            column,
+                        }) => Ok(Node::Call(function, Box::new(self.parenthesized(column)?))),
+                        _ => Err(invalid(
+                            token.column,
+                            "function arguments have to be in parentheses",
+                        )),
+                    }
+                }
+            },
+            _ => Err(invalid(
+                token.column,
+                "expected a number, a variable, a function or `(`",
+            )),
+        }
+    }
+
+    /// The rest of a parenthesized expression, after the `(` (which is at the
+    /// column given)
+    fn parenthesized(&mut self, open: usize) -> Result<Node> {
+        let node = self.expression()?;
+
+        match self.next() {
+            Some(&Token {
+                kind: TokenKind::Close,
+                ..
+            }) => Ok(node),
+            Some(token) => Err(invalid(token.column, "expected `)`")),
+            None => Err(invalid(open, "unclosed `(`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use color_scale::SimpleColorScale;
+    use complex_number::ComplexNumber;
+    use error::Error;
+    use expression::Expression;
+    use formula::{BurningShip, Formula, Multibrot, Tricorn};
+    use mandelbrot::{Mandelbrot, MandelbrotConfig, Viewport};
+    use std::sync::Arc;
+
+    fn points() -> Vec<ComplexNumber<f64>> {
+        vec![
+            ComplexNumber::new(0.0, 0.0),
+            ComplexNumber::new(0.5, -0.25),
+            ComplexNumber::new(-1.5, 2.0),
+            ComplexNumber::new(0.1, 0.7),
+        ]
+    }
+
+    fn close(a: ComplexNumber<f64>, b: ComplexNumber<f64>) -> bool {
+        (a - b).abs() <= 1e-12 * (1.0 + b.abs())
+    }
+
+    /// Checks an expression against a hand-written version at a few points
+    fn check<F: Fn(ComplexNumber<f64>, ComplexNumber<f64>) -> ComplexNumber<f64>>(
+        source: &str,
+        expected: F,
+    ) {
+        let expression = Expression::parse(source).unwrap();
+
+        for z in points() {
+            for c in points() {
+                let (got, want) = (expression.evaluate(z, c), expected(z, c));
+                assert!(
+                    close(got, want),
+                    "{} at z = {:?}, c = {:?}: {:?} != {:?}",
+                    source,
+                    z,
+                    c,
+                    got,
+                    want
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn evaluates_polynomials() {
+        check("z^2 + c", |z, c| z * z + c);
+        check("z^3 - z + c", |z, c| z * z * z - z + c);
+        check("z*z*z*z + c", |z, c| z * z * z * z + c);
+        check("2*z - 3.5 + c*1e-1", |z, c| z * 2.0 - 3.5 + c * 0.1);
+        check("(z + c) * (z - c)", |z, c| (z + c) * (z - c));
+        check("-z^2 + c", |z, c| -(z * z) + c);
+        check("z^2 + 0.5i*c", |z, c| {
+            z * z + ComplexNumber::new(0.0, 0.5) * c
+        });
+        check("2^3^2 + z", |z, _| z + 512.0);
+        check("z - c - 1", |z, c| z - c - 1.0);
+        check("z / 2 / 4", |z, _| z / 8.0);
+    }
+
+    #[test]
+    fn evaluates_functions() {
+        let i = ComplexNumber::new(0.0, 1.0);
+
+        check("sin(z) * c", |z, c| {
+            // sin(z) = (e^(iz) - e^(-iz)) / 2i
+            let sin = ((i * z).exp() - (-i * z).exp()) / (i * 2.0);
+            sin * c
+        });
+        check("exp(i * pi) + z", |z, _| z - 1.0);
+        check("conj(z)^2 + c", |z, c| Tricorn.iterate(z, c));
+        check("(abs(re(z)) + i*abs(im(z)))^2 + c", |z, c| {
+            BurningShip.iterate(z, c)
+        });
+        check("sqrt(z)^2", |z, _| z);
+        check("exp(ln(z + 2))", |z, _| z + 2.0);
+        check("z^0.5 * z^0.5", |z, _| z);
+        check("(z + 3)^-2 * (z + 3)^2 + c", |_, c| c + 1.0);
+    }
+
+    #[test]
+    fn evaluates_identities() {
+        let one = |_, _| ComplexNumber::new(1.0, 0.0);
+
+        check("sin(z)^2 + cos(z)^2", one);
+        check("cosh(z)^2 - sinh(z)^2", one);
+        check("tanh(z) * cosh(z) - sinh(z) + 1", one);
+        check("tan(z + 1) * cos(z + 1) - sin(z + 1) + 1", one);
+        check("e^z / exp(z)", one);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let column = |source: &str| match Expression::parse(source) {
+            Err(Error::InvalidExpression { column, .. }) => column,
+            other => panic!("{} parsed: {:?}", source, other),
+        };
+
+        assert_eq!(column("z^2 + $"), 7);
+        assert_eq!(column("z^2 + "), 7);
+        assert_eq!(column("z^2 + foo(z)"), 7);
+        assert_eq!(column("z^2 + sin z"), 7);
+        assert_eq!(column("(z^2 + c"), 1);
+        assert_eq!(column("z^2 + c)"), 8);
+        assert_eq!(column("z c"), 3);
+        assert_eq!(column("z * * c"), 5);
+        assert_eq!(column("1.2.3 + z"), 1);
+        assert_eq!(column(""), 1);
+        assert_eq!(column("sin(z c)"), 7);
+        // (Columns count characters, not bytes:)
+        assert_eq!(column("ζ^2"), 1);
+
+        let error = Expression::parse("z +").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid expression: unexpected end of expression (at column 4)"
+        );
+    }
+
+    #[test]
+    fn degrees() {
+        let degree = |source: &str| Expression::parse(source).unwrap().degree();
+
+        assert_eq!(degree("z^2 + c"), 2.0);
+        assert_eq!(degree("z^3 - z + c"), 3.0);
+        assert_eq!(degree("z*z*z*z*z + c"), 5.0);
+        assert_eq!(degree("(z^2 + c)^2.5"), 5.0);
+        assert_eq!(degree("conj(z)^4 / 3 + c"), 4.0);
+        assert_eq!(degree("z^(1 + 2) + sin(c)"), 3.0);
+        // Not polynomials (or not growing), so the guess is 2:
+        assert_eq!(degree("sin(z) * c"), 2.0);
+        assert_eq!(degree("z + c"), 2.0);
+        assert_eq!(degree("1 / z + c"), 2.0);
+    }
+
+    #[test]
+    fn renders_like_built_in_formulas() {
+        let dimensions = (30, 20);
+        let render = |formula: Arc<dyn Formula>| {
+            let mut config = MandelbrotConfig::<u8>::new(
+                dimensions,
+                Viewport::new(ComplexNumber::new(-2.5, 1.25), 3.5, 2.5),
+                Box::new(SimpleColorScale {}),
+            );
+            config.formula = formula;
+            config.interior_check = false;
+            config.periodicity_check = false;
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(100);
+            mandelbrot.get_orbits().clone()
+        };
+
+        let pairs: Vec<(&str, Arc<dyn Formula>)> = vec![
+            ("z^2 + c", Arc::new(Multibrot::new(2).unwrap())),
+            ("z^5 + c", Arc::new(Multibrot::new(5).unwrap())),
+            ("conj(z)^2 + c", Arc::new(Tricorn)),
+        ];
+
+        for (source, formula) in pairs {
+            let expression: Expression = source.parse().unwrap();
+            assert_eq!(render(Arc::new(expression)), render(formula), "{}", source);
+        }
+    }
+
+    #[test]
+    fn singular_expressions_escape() {
+        for source in ["1/z + c", "ln(z) + c", "0/0 + c"].iter() {
+            let mut config = MandelbrotConfig::<u8>::new(
+                (8, 6),
+                Viewport::new(ComplexNumber::new(-2.0, 1.0), 3.0, 2.0),
+                Box::new(SimpleColorScale {}),
+            );
+            config.formula = Arc::new(source.parse::<Expression>().unwrap());
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(50);
+
+            // Every orbit goes to infinity or NaN right away:
+            for orbit in mandelbrot.get_orbits().iter() {
+                assert!(orbit.iterations < 50, "{}", source);
+            }
+        }
+    }
+}
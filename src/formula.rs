@@ -0,0 +1,291 @@
+//! Iteration Formulas
+//!
+//! The Mandelbrot Set comes from iterating `z = z² + c`, but plenty of other
+//! formulas make nice pictures too. `Formula` is the trait the `Mandelbrot`
+//! type uses to run iterations; the usual Mandelbrot Set is `Multibrot::new(2)`
+//! (which is also `Multibrot::default()`, the default).
+
+use complex_number::ComplexNumber;
+use error::{Error, Result};
+
+/// A formula to iterate
+///
+/// Besides the step itself, a formula says when an orbit has escaped and how
+/// fast orbits grow once they have (the degree, which is what smooth coloring
+/// needs to know). Formulas whose step is complex differentiable can also
+/// provide the derivative so that the periodicity check can tell attracting
+/// cycles from repelling ones; without it, the periodicity check is skipped.
+pub trait Formula: Send + Sync {
+    /// One step of the iteration
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64>;
+
+    /// Whether `z` has escaped, given the (squared) escape radius the
+    /// `Mandelbrot` was configured with
+    ///
+    /// By default, a point has escaped once its magnitude reaches the escape
+    /// radius.
+    fn escaped(&self, z: ComplexNumber<f64>, escape_radius_sqr: f64) -> bool {
+        z.norm_sqr() >= escape_radius_sqr
+    }
+
+    /// How fast orbits grow once they've escaped: each step takes `|z|` to
+    /// (roughly) `|z|^degree`
+    ///
+    /// Smooth coloring uses this to work out fractional iteration counts.
+    fn degree(&self) -> f64 {
+        2.0
+    }
+
+    /// The derivative of the step with respect to `z`, if the step has one
+    fn derivative(&self, _z: ComplexNumber<f64>) -> Option<ComplexNumber<f64>> {
+        None
+    }
+
+    /// Whether this is exactly `z² + c`
+    ///
+    /// The cardioid/bulb check and perturbation rendering only know how to
+    /// deal with the usual Mandelbrot Set.
+    fn is_quadratic(&self) -> bool {
+        false
+    }
+}
+
+/// `z^n + c` for a whole number `n` (at least 2); `n = 2` is the usual
+/// Mandelbrot Set
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Multibrot {
+    /// The power to raise `z` to
+    power: u32,
+}
+
+impl Multibrot {
+    /// Makes a Multibrot formula for the power given
+    ///
+    /// Fails if `power` is less than 2 (`z + c` and `c` aren't interesting).
+    pub fn new(power: u32) -> Result<Multibrot> {
+        if power < 2 {
+            return Err(Error::InvalidConfig(
+                "Multibrot powers have to be at least 2",
+            ));
+        }
+
+        Ok(Multibrot { power })
+    }
+
+    /// The power `z` is raised to
+    pub fn power(&self) -> u32 {
+        self.power
+    }
+}
+
+/// The usual Mandelbrot Set: `z² + c`
+impl Default for Multibrot {
+    fn default() -> Multibrot {
+        Multibrot { power: 2 }
+    }
+}
+
+impl Formula for Multibrot {
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        match self.power {
+            // (This is the one that matters, so we make sure it's exactly the
+            // same as the usual formula)
+            2 => z * z + c,
+            n => z.powi(n as i32) + c,
+        }
+    }
+
+    fn degree(&self) -> f64 {
+        f64::from(self.power)
+    }
+
+    fn derivative(&self, z: ComplexNumber<f64>) -> Option<ComplexNumber<f64>> {
+        Some(z.powi(self.power as i32 - 1) * f64::from(self.power))
+    }
+
+    fn is_quadratic(&self) -> bool {
+        self.power == 2
+    }
+}
+
+/// `z^n + c` for a real `n` greater than 1 (using the principal branch)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RealMultibrot {
+    /// The power to raise `z` to
+    power: f64,
+}
+
+impl RealMultibrot {
+    /// Makes a Multibrot formula for the power given
+    ///
+    /// Fails if `power` isn't finite and greater than 1 (with smaller powers
+    /// nothing ever escapes).
+    pub fn new(power: f64) -> Result<RealMultibrot> {
+        if !power.is_finite() || power <= 1.0 {
+            return Err(Error::InvalidConfig(
+                "Multibrot powers have to be finite and greater than 1",
+            ));
+        }
+
+        Ok(RealMultibrot { power })
+    }
+
+    /// The power `z` is raised to
+    pub fn power(&self) -> f64 {
+        self.power
+    }
+}
+
+impl Formula for RealMultibrot {
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        z.powf(self.power) + c
+    }
+
+    /// With powers close to 1, points have to get a lot further out than 2
+    /// before they're sure to escape: once `|z| > 2^(1/(n-1))` (and `|z| >
+    /// |c|`), `|z|` keeps growing.
+    fn escaped(&self, z: ComplexNumber<f64>, escape_radius_sqr: f64) -> bool {
+        let minimum = 4f64.powf(1.0 / (self.power - 1.0));
+        z.norm_sqr() >= escape_radius_sqr.max(minimum)
+    }
+
+    fn degree(&self) -> f64 {
+        self.power
+    }
+
+    fn derivative(&self, z: ComplexNumber<f64>) -> Option<ComplexNumber<f64>> {
+        Some(z.powf(self.power - 1.0) * self.power)
+    }
+}
+
+/// The Burning Ship: `(|Re z| + i|Im z|)² + c`
+///
+/// (The ship is upside down unless the imaginary axis is flipped.)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurningShip;
+
+impl Formula for BurningShip {
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        let z = ComplexNumber::new(z.real().abs(), z.imag().abs());
+        z * z + c
+    }
+}
+
+/// The Tricorn (or Mandelbar): `conj(z)² + c`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tricorn;
+
+impl Formula for Tricorn {
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        let z = z.conj();
+        z * z + c
+    }
+}
+
+/// The Celtic Mandelbrot: `|Re(z²)| + i Im(z²) + c`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Celtic;
+
+impl Formula for Celtic {
+    fn iterate(&self, z: ComplexNumber<f64>, c: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        let z = z * z;
+        ComplexNumber::new(z.real().abs(), z.imag()) + c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use complex_number::ComplexNumber;
+    use formula::{BurningShip, Celtic, Formula, Multibrot, RealMultibrot, Tricorn};
+
+    fn close(a: ComplexNumber<f64>, b: ComplexNumber<f64>) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn steps() {
+        let z = ComplexNumber::new(-0.5, 1.5);
+        let c = ComplexNumber::new(0.25, -0.75);
+
+        // z² = -2 - 1.5i
+        assert_eq!(
+            Multibrot::new(2).unwrap().iterate(z, c),
+            ComplexNumber::new(-1.75, -2.25)
+        );
+        // z³ = z² * z = (-2 - 1.5i)(-0.5 + 1.5i) = 3.25 - 2.25i
+        assert_eq!(
+            Multibrot::new(3).unwrap().iterate(z, c),
+            ComplexNumber::new(3.5, -3.0)
+        );
+        // (0.5 + 1.5i)² = -2 + 1.5i
+        assert_eq!(BurningShip.iterate(z, c), ComplexNumber::new(-1.75, 0.75));
+        // (-0.5 - 1.5i)² = -2 + 1.5i
+        assert_eq!(Tricorn.iterate(z, c), ComplexNumber::new(-1.75, 0.75));
+        // |-2| - 1.5i
+        assert_eq!(Celtic.iterate(z, c), ComplexNumber::new(2.25, -2.25));
+
+        assert!(close(
+            RealMultibrot::new(2.0).unwrap().iterate(z, c),
+            Multibrot::new(2).unwrap().iterate(z, c)
+        ));
+        assert!(close(
+            RealMultibrot::new(3.0).unwrap().iterate(z, c),
+            Multibrot::new(3).unwrap().iterate(z, c)
+        ));
+        assert_eq!(
+            RealMultibrot::new(2.5)
+                .unwrap()
+                .iterate(ComplexNumber::new(0.0, 0.0), c),
+            c
+        );
+    }
+
+    #[test]
+    fn degrees_and_derivatives() {
+        let z = ComplexNumber::new(0.3, -0.2);
+
+        assert_eq!(Multibrot::new(5).unwrap().degree(), 5.0);
+        assert_eq!(RealMultibrot::new(2.5).unwrap().degree(), 2.5);
+        assert_eq!(BurningShip.degree(), 2.0);
+
+        assert_eq!(Multibrot::new(2).unwrap().derivative(z), Some(z * 2.0));
+        assert!(close(
+            RealMultibrot::new(3.0).unwrap().derivative(z).unwrap(),
+            Multibrot::new(3).unwrap().derivative(z).unwrap()
+        ));
+        assert_eq!(Tricorn.derivative(z), None);
+
+        assert!(Multibrot::new(2).unwrap().is_quadratic());
+        assert!(!Multibrot::new(3).unwrap().is_quadratic());
+        assert!(!Celtic.is_quadratic());
+    }
+
+    #[test]
+    fn escape_rules() {
+        let z = ComplexNumber::new(3.0, 0.0);
+
+        assert!(Multibrot::new(4).unwrap().escaped(z, 4.0));
+        assert!(!Multibrot::new(4).unwrap().escaped(z, 16.0));
+
+        // z^1.5 only escapes for sure once |z| > 4:
+        assert!(!RealMultibrot::new(1.5).unwrap().escaped(z, 4.0));
+        assert!(RealMultibrot::new(1.5)
+            .unwrap()
+            .escaped(ComplexNumber::new(4.0, 0.0), 4.0));
+    }
+
+    #[test]
+    fn invalid_powers() {
+        assert!(Multibrot::new(0).is_err());
+        assert!(Multibrot::new(1).is_err());
+        assert_eq!(Multibrot::default(), Multibrot::new(2).unwrap());
+        assert_eq!(Multibrot::new(7).unwrap().power(), 7);
+
+        assert!(RealMultibrot::new(1.0).is_err());
+        assert!(RealMultibrot::new(-3.0).is_err());
+        assert!(RealMultibrot::new(f64::NAN).is_err());
+        assert!(RealMultibrot::new(f64::INFINITY).is_err());
+        assert_eq!(RealMultibrot::new(1.5).unwrap().power(), 1.5);
+    }
+}
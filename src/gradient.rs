@@ -0,0 +1,433 @@
+//! Gradient Palettes
+//!
+//! A `Gradient` is a palette made of color stops: colors placed at positions
+//! from 0 to 1, with colors in between the stops blended in one of a few
+//! color spaces. Positions outside of `[0, 1]` are brought back in by
+//! clamping, repeating or mirroring the gradient, which is what lets
+//! (smoothed) iteration counts, which go well past 1, run through a gradient
+//! over and over.
+
+extern crate num_traits;
+
+use self::num_traits::{AsPrimitive, Bounded, Unsigned};
+use error::{Error, Result};
+use pixel::{
+    hsv_to_rgb, linear_to_srgb, oklab_to_rgb, rgb_to_hsv, rgb_to_oklab, srgb_to_linear, Pixel,
+    PixelMath,
+};
+use std::fmt::UpperHex;
+
+/// A color with sRGB channels and alpha, each from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl Color {
+    /// An opaque color
+    pub fn new(r: f64, g: f64, b: f64) -> Color {
+        Color::new_rgba(r, g, b, 1.0)
+    }
+
+    pub fn new_rgba(r: f64, g: f64, b: f64, a: f64) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// An opaque color from 8 bit channels
+    pub fn from_rgb8(r: u8, g: u8, b: u8) -> Color {
+        Color::new(
+            f64::from(r) / 255.0,
+            f64::from(g) / 255.0,
+            f64::from(b) / 255.0,
+        )
+    }
+
+    /// The channels of a pixel, scaled to be from 0 to 1
+    pub fn from_pixel<T>(pixel: &Pixel<T>) -> Color
+    where
+        T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+    {
+        let max: f64 = T::max_value().into();
+        let [r, g, b, a] = pixel.get_slice();
+
+        Color::new_rgba(
+            r.into() / max,
+            g.into() / max,
+            b.into() / max,
+            a.into() / max,
+        )
+    }
+
+    /// The color as a pixel (channels outside of `[0, 1]` are clamped)
+    pub fn to_pixel<T>(self) -> Pixel<T>
+    where
+        T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let max: f64 = T::max_value().into();
+        let channel = |v: f64| -> T { (v.clamp(0.0, 1.0) * max).round().as_() };
+
+        Pixel::new_rgba(
+            channel(self.r),
+            channel(self.g),
+            channel(self.b),
+            channel(self.a),
+        )
+    }
+
+    /// Whether every channel is finite
+    fn is_finite(self) -> bool {
+        [self.r, self.g, self.b, self.a]
+            .iter()
+            .all(|v| v.is_finite())
+    }
+}
+
+/// The color space colors between stops are blended in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// Straight between the sRGB values; this is what most fractal programs
+    /// do
+    Rgb,
+    /// In linear light, which keeps blends between bright colors from
+    /// dipping in the middle
+    LinearRgb,
+    /// Around the color wheel (the shorter way around), which keeps blends
+    /// between saturated colors saturated
+    Hsv,
+    /// In OKLab, which keeps the perceived lightness changing evenly
+    OkLab,
+}
+
+/// What happens to positions outside of `[0, 1]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    /// They get the color at the nearest end
+    Clamp,
+    /// The gradient starts over: 1.25 is the same as 0.25
+    Repeat,
+    /// The gradient goes back and forth: 1.25 is the same as 0.75
+    Mirror,
+}
+
+/// A palette made of color stops
+///
+/// A position `t` is looked up by first working out `t * density + offset`
+/// and then wrapping that into `[0, 1]` (see `Wrap`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// The stops, sorted by position
+    stops: Vec<(f64, Color)>,
+    /// The color space colors are blended in
+    pub interpolation: Interpolation,
+    /// What to do with positions outside of `[0, 1]`
+    pub wrap: Wrap,
+    /// Added to positions (after they're scaled by the density)
+    pub offset: f64,
+    /// How many times positions are scaled up: a density of 2 goes through
+    /// the gradient twice as fast
+    pub density: f64,
+}
+
+impl Gradient {
+    /// Makes a gradient with the stops (positions and colors) given, blended
+    /// in sRGB, clamped, with no offset and a density of 1
+    ///
+    /// Stops at the same position make a hard edge. Fails if there are no
+    /// stops, if the positions aren't in order or aren't all within `[0, 1]`
+    /// or if any of the colors aren't finite.
+    pub fn new(stops: Vec<(f64, Color)>) -> Result<Gradient> {
+        if stops.is_empty() {
+            return Err(Error::InvalidGradient("a gradient needs at least one stop"));
+        }
+
+        if stops
+            .iter()
+            .any(|(position, _)| !(0.0..=1.0).contains(position))
+        {
+            return Err(Error::InvalidGradient(
+                "stop positions have to be between 0 and 1",
+            ));
+        }
+
+        if stops.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err(Error::InvalidGradient("stops have to be in order"));
+        }
+
+        if stops.iter().any(|(_, color)| !color.is_finite()) {
+            return Err(Error::InvalidGradient("stop colors have to be finite"));
+        }
+
+        Ok(Gradient {
+            stops,
+            interpolation: Interpolation::Rgb,
+            wrap: Wrap::Clamp,
+            offset: 0.0,
+            density: 1.0,
+        })
+    }
+
+    /// Spaces the colors given out evenly from 0 to 1
+    pub fn evenly_spaced(colors: &[Color]) -> Result<Gradient> {
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+
+        Gradient::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, color)| (i as f64 / last, *color))
+                .collect(),
+        )
+    }
+
+    /// The stops, sorted by position
+    pub fn stops(&self) -> &[(f64, Color)] {
+        &self.stops
+    }
+
+    /// The color at position `t` (positions that aren't finite get the color
+    /// at 0)
+    pub fn color_at(&self, t: f64) -> Color {
+        let t = t * self.density + self.offset;
+        let t = if t.is_finite() { t } else { 0.0 };
+
+        let t = match self.wrap {
+            Wrap::Clamp => t.clamp(0.0, 1.0),
+            Wrap::Repeat => t.rem_euclid(1.0),
+            Wrap::Mirror => {
+                let t = t.rem_euclid(2.0);
+                if t > 1.0 {
+                    2.0 - t
+                } else {
+                    t
+                }
+            }
+        };
+
+        // The first stop past `t` (with hard edges, the stop after the edge):
+        let next = self.stops.iter().position(|(position, _)| *position > t);
+
+        match next {
+            None => self.stops[self.stops.len() - 1].1,
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let ((start, from), (end, to)) = (self.stops[i - 1], self.stops[i]);
+                self.interpolation
+                    .blend(from, to, (t - start) / (end - start))
+            }
+        }
+    }
+
+    /// The color at position `t`, as a pixel
+    pub fn pixel_at<T>(&self, t: f64) -> Pixel<T>
+    where
+        T: 'static + Unsigned + Bounded + Copy + UpperHex + Into<f64>,
+        f64: AsPrimitive<T>,
+    {
+        self.color_at(t).to_pixel()
+    }
+}
+
+impl Interpolation {
+    /// The color `f` of the way from `from` to `to`
+    fn blend(self, from: Color, to: Color, f: f64) -> Color {
+        let lerp = |a: f64, b: f64| a + (b - a) * f;
+        let alpha = lerp(from.a, to.a);
+
+        let (r, g, b) = match self {
+            Interpolation::Rgb => (lerp(from.r, to.r), lerp(from.g, to.g), lerp(from.b, to.b)),
+            Interpolation::LinearRgb => (
+                linear_to_srgb(lerp(srgb_to_linear(from.r), srgb_to_linear(to.r))),
+                linear_to_srgb(lerp(srgb_to_linear(from.g), srgb_to_linear(to.g))),
+                linear_to_srgb(lerp(srgb_to_linear(from.b), srgb_to_linear(to.b))),
+            ),
+            Interpolation::Hsv => {
+                let ((h1, s1, v1), (h2, s2, v2)) = (
+                    rgb_to_hsv(from.r, from.g, from.b),
+                    rgb_to_hsv(to.r, to.g, to.b),
+                );
+
+                // Grays don't have a hue, so they take on the other color's:
+                let (h1, h2) = match (s1 == 0.0, s2 == 0.0) {
+                    (true, false) => (h2, h2),
+                    (false, true) => (h1, h1),
+                    _ => (h1, h2),
+                };
+
+                // The shorter way around:
+                let delta = (h2 - h1 + 540.0).rem_euclid(360.0) - 180.0;
+                hsv_to_rgb(
+                    (h1 + delta * f).rem_euclid(360.0),
+                    lerp(s1, s2),
+                    lerp(v1, v2),
+                )
+            }
+            Interpolation::OkLab => {
+                let (l1, a1, b1) = rgb_to_oklab(from.r, from.g, from.b);
+                let (l2, a2, b2) = rgb_to_oklab(to.r, to.g, to.b);
+                oklab_to_rgb(lerp(l1, l2), lerp(a1, a2), lerp(b1, b2))
+            }
+        };
+
+        Color::new_rgba(r, g, b, alpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use error::Error;
+    use gradient::{Color, Gradient, Interpolation, Wrap};
+    use pixel::{Pixel, PixelMath};
+
+    fn close(a: Color, b: Color) -> bool {
+        [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)]
+            .iter()
+            .all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    fn black_to_white() -> Gradient {
+        Gradient::new(vec![
+            (0.0, Color::new(0.0, 0.0, 0.0)),
+            (1.0, Color::new(1.0, 1.0, 1.0)),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn stops() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let gradient = Gradient::new(vec![
+            (0.25, red),
+            (0.5, blue),
+            (0.5, Color::new_rgba(0.0, 1.0, 0.0, 0.0)),
+        ])
+        .unwrap();
+
+        // Past the ends, the colors at the ends:
+        assert_eq!(gradient.color_at(0.0), red);
+        assert_eq!(gradient.color_at(1.0), Color::new_rgba(0.0, 1.0, 0.0, 0.0));
+
+        assert!(close(gradient.color_at(0.375), Color::new(0.5, 0.0, 0.5)));
+
+        // Stops at the same place make a hard edge:
+        assert!(close(gradient.color_at(0.4999999999), blue));
+        assert_eq!(gradient.color_at(0.5), gradient.color_at(0.75));
+
+        assert_eq!(
+            Gradient::evenly_spaced(&[red, blue, red]).unwrap().stops(),
+            &[(0.0, red), (0.5, blue), (1.0, red)]
+        );
+        assert_eq!(Gradient::new(vec![(0.5, red)]).unwrap().color_at(0.9), red);
+    }
+
+    #[test]
+    fn invalid_stops() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let invalid = |stops: Vec<(f64, Color)>| match Gradient::new(stops) {
+            Err(Error::InvalidGradient(_)) => {}
+            other => panic!("{:?}", other),
+        };
+
+        invalid(vec![]);
+        invalid(vec![(0.5, red), (0.25, red)]);
+        invalid(vec![(0.0, red), (1.5, red)]);
+        invalid(vec![(f64::NAN, red)]);
+        invalid(vec![(0.0, Color::new(f64::INFINITY, 0.0, 0.0))]);
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut gradient = black_to_white();
+        let gray = |t: f64, gradient: &Gradient| gradient.color_at(t).r;
+
+        assert_eq!(gray(-1.0, &gradient), 0.0);
+        assert_eq!(gray(1.25, &gradient), 1.0);
+        assert_eq!(gray(f64::NAN, &gradient), 0.0);
+
+        gradient.wrap = Wrap::Repeat;
+        assert_eq!(gray(1.25, &gradient), 0.25);
+        assert_eq!(gray(-0.25, &gradient), 0.75);
+
+        gradient.wrap = Wrap::Mirror;
+        assert_eq!(gray(1.25, &gradient), 0.75);
+        assert_eq!(gray(2.25, &gradient), 0.25);
+        assert_eq!(gray(-0.25, &gradient), 0.25);
+
+        // Twice as dense and shifted over by a quarter:
+        gradient.wrap = Wrap::Repeat;
+        gradient.density = 2.0;
+        gradient.offset = 0.25;
+        assert_eq!(gray(0.25, &gradient), 0.75);
+        assert_eq!(gray(0.5, &gradient), 0.25);
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut gradient = black_to_white();
+        let middle = |gradient: &Gradient| gradient.pixel_at::<u8>(0.5);
+
+        assert_eq!(middle(&gradient), Pixel::new(128, 128, 128));
+
+        // Half way in linear light is brighter:
+        gradient.interpolation = Interpolation::LinearRgb;
+        assert_eq!(middle(&gradient), Pixel::new(188, 188, 188));
+
+        // OKLab's lightness follows perceived lightness, which puts the
+        // middle at a darker gray (its lightness is half way, at 0.5):
+        gradient.interpolation = Interpolation::OkLab;
+        assert_eq!(middle(&gradient), Pixel::new(99, 99, 99));
+
+        // HSV goes around the color wheel, the shorter way:
+        let mut hues = Gradient::new(vec![
+            (0.0, Color::new(1.0, 0.0, 0.0)),
+            (1.0, Color::new(1.0, 0.0, 1.0)),
+        ])
+        .unwrap();
+        hues.interpolation = Interpolation::Hsv;
+        assert!(close(hues.color_at(0.5), Color::new(1.0, 0.0, 0.5)));
+
+        let mut hues = Gradient::new(vec![
+            (0.0, Color::new(1.0, 0.0, 0.0)),
+            (1.0, Color::new(0.0, 0.0, 1.0)),
+        ])
+        .unwrap();
+        hues.interpolation = Interpolation::Hsv;
+        assert!(close(hues.color_at(0.5), Color::new(1.0, 0.0, 1.0)));
+        hues.interpolation = Interpolation::Rgb;
+        assert!(close(hues.color_at(0.5), Color::new(0.5, 0.0, 0.5)));
+
+        // Every color space gets the stops themselves right:
+        for interpolation in [
+            Interpolation::Rgb,
+            Interpolation::LinearRgb,
+            Interpolation::Hsv,
+            Interpolation::OkLab,
+        ]
+        .iter()
+        {
+            let orange = Color::from_rgb8(255, 128, 51);
+            let mut gradient =
+                Gradient::new(vec![(0.0, orange), (1.0, Color::new(0.1, 0.6, 0.3))]).unwrap();
+            gradient.interpolation = *interpolation;
+
+            assert_eq!(gradient.pixel_at::<u8>(0.0), orange.to_pixel());
+        }
+    }
+
+    #[test]
+    fn pixels() {
+        let color = Color::from_pixel(&Pixel::new_rgba(255u8, 0, 51, 102));
+        assert!(close(color, Color::new_rgba(1.0, 0.0, 0.2, 0.4)));
+        assert_eq!(color.to_pixel::<u8>(), Pixel::new_rgba(255, 0, 51, 102));
+        assert_eq!(
+            Color::new(1.5, -0.5, 0.5).to_pixel::<u8>(),
+            Pixel::new(255, 0, 128)
+        );
+        assert_eq!(Color::from_rgb8(255, 0, 51), Color::new(1.0, 0.0, 0.2));
+    }
+}
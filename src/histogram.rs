@@ -0,0 +1,156 @@
+//! Histogram Coloring
+//!
+//! Coloring by `iterations / max_iterations` spreads colors over the whole
+//! range of iteration counts, even though most points escape within the
+//! first few iterations; every time more iterations are run (or the view
+//! zooms in) the colors shift. A `Histogram` instead ranks each point
+//! against all the other points that escaped, so colors follow how the
+//! points in the image are actually distributed and barely move when more
+//! iterations are run.
+
+use mandelbrot::Orbit;
+
+/// The number of escaped points at each iteration count, totaled up
+///
+/// `Mandelbrot` makes one of these (in a second pass over the orbits, after
+/// the iterations are run) when `MandelbrotConfig::histogram` is set and
+/// hands it to coloring functions in `RenderInfo::histogram`. Points that
+/// haven't escaped aren't counted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Each iteration count that some point escaped with (in order) and the
+    /// number of escaped points with fewer iterations than that
+    ///
+    /// Only the counts that actually show up are kept, so deep zooms with
+    /// huge iteration limits don't need a slot for every possible count.
+    levels: Vec<(u32, u64)>,
+    /// The number of points that escaped
+    total: u64,
+}
+
+impl Histogram {
+    /// Counts up the orbits that escaped: the ones with fewer than
+    /// `max_iterations` iterations
+    pub fn new<'a, I: IntoIterator<Item = &'a Orbit>>(orbits: I, max_iterations: u32) -> Histogram {
+        let mut escaped: Vec<u32> = orbits
+            .into_iter()
+            .map(|orbit| orbit.iterations)
+            .filter(|&iterations| iterations < max_iterations)
+            .collect();
+        escaped.sort_unstable();
+
+        let mut levels: Vec<(u32, u64)> = Vec::new();
+        for (below, &iterations) in escaped.iter().enumerate() {
+            if levels.last().map(|&(last, _)| last) != Some(iterations) {
+                levels.push((iterations, below as u64));
+            }
+        }
+
+        Histogram {
+            levels,
+            total: escaped.len() as u64,
+        }
+    }
+
+    /// The number of points that escaped
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The fraction of the escaped points with fewer iterations than
+    /// `iterations` (between 0 and 1), where the points with `n` iterations
+    /// count as being spread evenly from `n` to `n + 1`
+    ///
+    /// That way smoothed (fractional) iteration counts get ranks that vary
+    /// smoothly too. Everything gets a rank of 0 if no points have escaped.
+    pub fn rank(&self, iterations: f64) -> f64 {
+        let total = self.total();
+        if total == 0 || iterations.is_nan() {
+            return 0.0;
+        }
+
+        let iterations = iterations.clamp(0.0, f64::from(u32::MAX));
+        let i = iterations.floor() as u32;
+
+        // The points below level `k` (all of them past the last level):
+        let below_level = |k: usize| self.levels.get(k).map_or(total, |&(_, below)| below);
+
+        let below = match self.levels.binary_search_by_key(&i, |&(n, _)| n) {
+            Ok(k) => {
+                let count = (below_level(k + 1) - below_level(k)) as f64;
+                below_level(k) as f64 + (iterations - f64::from(i)) * count
+            }
+            Err(k) => below_level(k) as f64,
+        };
+
+        below / total as f64
+    }
+
+    /// The rank of an escaped orbit's (whole) iteration count: the fraction
+    /// of the escaped points that took no more iterations to escape than it
+    /// did
+    pub fn rank_of(&self, orbit: &Orbit) -> f64 {
+        self.rank(f64::from(orbit.iterations) + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use histogram::Histogram;
+    use mandelbrot::Orbit;
+
+    fn orbits(iterations: &[u32]) -> Vec<Orbit> {
+        iterations
+            .iter()
+            .map(|i| {
+                let mut orbit = Orbit::default();
+                orbit.iterations = *i;
+                orbit
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ranks() {
+        // Two points escape after 1 iteration, one after 2 and one after 4;
+        // the 10s haven't escaped:
+        let orbits = orbits(&[1, 2, 10, 1, 4, 10]);
+        let histogram = Histogram::new(&orbits, 10);
+
+        assert_eq!(histogram.total(), 4);
+        assert_eq!(histogram.rank(0.0), 0.0);
+        assert_eq!(histogram.rank(1.0), 0.0);
+        assert_eq!(histogram.rank(1.5), 0.25);
+        assert_eq!(histogram.rank(2.0), 0.5);
+        assert_eq!(histogram.rank(3.5), 0.75);
+        assert_eq!(histogram.rank(4.5), 0.875);
+        assert_eq!(histogram.rank(100.0), 1.0);
+        assert_eq!(histogram.rank(-3.0), 0.0);
+
+        let ranks: Vec<f64> = orbits[..5].iter().map(|o| histogram.rank_of(o)).collect();
+        assert_eq!(ranks, vec![0.5, 0.75, 1.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn nothing_escaped() {
+        let histogram = Histogram::new(&orbits(&[5, 5]), 5);
+
+        assert_eq!(histogram.total(), 0);
+        assert_eq!(histogram.rank(3.0), 0.0);
+        assert_eq!(Histogram::new(&[], 0).rank(0.0), 0.0);
+    }
+
+    #[test]
+    fn huge_limits() {
+        // Only the counts that show up take any room:
+        let orbits = orbits(&[3, 3_000_000_000, u32::MAX]);
+        let histogram = Histogram::new(&orbits, u32::MAX);
+
+        assert_eq!(histogram.total(), 2);
+        assert_eq!(histogram.rank(3.5), 0.25);
+        assert_eq!(histogram.rank(1e9), 0.5);
+        assert_eq!(histogram.rank(3e9 + 0.5), 0.75);
+        assert_eq!(histogram.rank(1e12), 1.0);
+    }
+}
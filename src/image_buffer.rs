@@ -0,0 +1,307 @@
+//! A Flat Image Buffer
+//!
+//! `ImageBuffer` holds a grid of values (`Pixel`s, `Orbit`s, whatever) in a
+//! single row-major allocation. Buffers of `Pixel<u8>`s can be viewed as RGBA
+//! bytes without copying them, which is what PNG and GIF encoders want.
+
+use error::{Error, Result};
+use pixel::Pixel;
+use std::{
+    mem,
+    ops::{Index, IndexMut},
+    slice,
+};
+
+/// A grid of values stored row by row in one contiguous `Vec`
+///
+/// Rows start `stride` elements apart; `stride` is at least `width` and any
+/// elements past `width` in a row are padding. Buffers made with
+/// `ImageBuffer::new` have no padding (`stride == width`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageBuffer<T> {
+    width: u32,
+    height: u32,
+    stride: usize,
+    data: Vec<T>,
+}
+
+impl<T: Clone> ImageBuffer<T> {
+    /// Makes a `width` by `height` buffer with every element set to `fill`
+    ///
+    /// Fails if the buffer would be too big to allocate.
+    pub fn new(width: u32, height: u32, fill: T) -> Result<ImageBuffer<T>> {
+        Self::with_stride(width, height, width as usize, fill)
+    }
+
+    /// Makes a `width` by `height` buffer whose rows start `stride` elements
+    /// apart, with every element (padding included) set to `fill`
+    ///
+    /// Fails if the buffer would be too big to allocate. Panics if `stride`
+    /// is less than `width`.
+    pub fn with_stride(width: u32, height: u32, stride: usize, fill: T) -> Result<ImageBuffer<T>> {
+        assert!(
+            stride >= width as usize,
+            "stride ({}) must be at least the width ({})",
+            stride,
+            width
+        );
+
+        let len = stride.checked_mul(height as usize);
+        let len = match (
+            len,
+            len.and_then(|len| len.checked_mul(mem::size_of::<T>())),
+        ) {
+            (Some(len), Some(bytes)) if bytes <= isize::MAX as usize => len,
+            _ => return Err(Error::DimensionsTooLarge { width, height }),
+        };
+
+        Ok(ImageBuffer {
+            width,
+            height,
+            stride,
+            data: vec![fill; len],
+        })
+    }
+
+    /// Sets every element (padding included) to `value`
+    pub fn fill(&mut self, value: T) {
+        for element in self.data.iter_mut() {
+            *element = value.clone();
+        }
+    }
+}
+
+impl<T> ImageBuffer<T> {
+    /// The number of elements in each row
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The number of rows
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The width and the height
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The number of elements from the start of one row to the start of the
+    /// next one
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// The element at column `x` and row `y`, if there is one
+    pub fn get(&self, x: u32, y: u32) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.data[self.offset(x, y)])
+        } else {
+            None
+        }
+    }
+
+    /// A mutable reference to the element at column `x` and row `y`, if there
+    /// is one
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            let offset = self.offset(x, y);
+            Some(&mut self.data[offset])
+        } else {
+            None
+        }
+    }
+
+    /// Row `y` (without any padding)
+    ///
+    /// Panics if `y` is out of bounds.
+    pub fn row(&self, y: u32) -> &[T] {
+        assert!(y < self.height, "row {} is out of bounds", y);
+
+        let start = y as usize * self.stride;
+        &self.data[start..start + self.width as usize]
+    }
+
+    /// Row `y` (without any padding), mutably
+    ///
+    /// Panics if `y` is out of bounds.
+    pub fn row_mut(&mut self, y: u32) -> &mut [T] {
+        assert!(y < self.height, "row {} is out of bounds", y);
+
+        let start = y as usize * self.stride;
+        &mut self.data[start..start + self.width as usize]
+    }
+
+    /// Iterates over the rows, top to bottom (without any padding)
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        let width = self.width as usize;
+
+        // (`chunks` doesn't like 0, which is the stride of a 0 wide buffer)
+        self.data
+            .chunks(self.stride.max(1))
+            .map(move |row| &row[..width])
+    }
+
+    /// Iterates over the rows, top to bottom (without any padding), mutably
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        let width = self.width as usize;
+
+        self.data
+            .chunks_mut(self.stride.max(1))
+            .map(move |row| &mut row[..width])
+    }
+
+    /// Iterates over every element, row by row (skipping any padding)
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.rows().flat_map(|row| row.iter())
+    }
+
+    /// Iterates over every element, row by row (skipping any padding),
+    /// mutably
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.rows_mut().flat_map(|row| row.iter_mut())
+    }
+
+    /// All the elements, padding included
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// All the elements, padding included, mutably
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Gives back the underlying `Vec`
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        y as usize * self.stride + x as usize
+    }
+}
+
+impl<T> Index<(u32, u32)> for ImageBuffer<T> {
+    type Output = T;
+
+    /// Indexes by `(x, y)`; panics if that's out of bounds
+    fn index(&self, (x, y): (u32, u32)) -> &T {
+        self.get(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) is out of bounds", x, y))
+    }
+}
+
+impl<T> IndexMut<(u32, u32)> for ImageBuffer<T> {
+    fn index_mut(&mut self, (x, y): (u32, u32)) -> &mut T {
+        self.get_mut(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) is out of bounds", x, y))
+    }
+}
+
+impl ImageBuffer<Pixel<u8>> {
+    /// The whole buffer as RGBA bytes, without copying anything
+    ///
+    /// Rows start `stride() * 4` bytes apart; for buffers without padding
+    /// this can go straight to an image encoder.
+    pub fn as_rgba_bytes(&self) -> &[u8] {
+        // `Pixel` is `repr(C)` and made of four `T`s, so a `Pixel<u8>` is
+        // exactly 4 bytes, laid out as R, G, B, A, with no padding:
+        debug_assert_eq!(mem::size_of::<Pixel<u8>>(), 4);
+
+        unsafe { slice::from_raw_parts(self.data.as_ptr() as *const u8, self.data.len() * 4) }
+    }
+
+    /// The whole buffer as RGBA bytes, mutably and without copying anything
+    pub fn as_rgba_bytes_mut(&mut self) -> &mut [u8] {
+        debug_assert_eq!(mem::size_of::<Pixel<u8>>(), 4);
+
+        unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut u8, self.data.len() * 4) }
+    }
+
+    /// Row `y` as RGBA bytes (without any padding), without copying anything
+    pub fn row_rgba_bytes(&self, y: u32) -> &[u8] {
+        let start = y as usize * self.stride * 4;
+        &self.as_rgba_bytes()[start..start + self.width as usize * 4]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use error::{Error, Result};
+    use image_buffer::ImageBuffer;
+    use pixel::{Pixel, PixelMath};
+
+    #[test]
+    fn accessors() {
+        let mut buffer = ImageBuffer::new(3, 2, 0u32).unwrap();
+        assert_eq!(buffer.dimensions(), (3, 2));
+        assert_eq!(buffer.stride(), 3);
+
+        for (i, element) in buffer.iter_mut().enumerate() {
+            *element = i as u32;
+        }
+
+        assert_eq!(buffer.row(1), &[3, 4, 5]);
+        assert_eq!(buffer[(2, 0)], 2);
+        assert_eq!(buffer.get(1, 1), Some(&4));
+        assert_eq!(buffer.get(3, 0), None);
+        assert_eq!(buffer.get(0, 2), None);
+
+        buffer[(0, 1)] = 30;
+        buffer.row_mut(0)[1] = 10;
+        assert_eq!(buffer.as_slice(), &[0, 10, 2, 30, 4, 5]);
+
+        let rows: Vec<&[u32]> = buffer.rows().collect();
+        assert_eq!(rows, vec![&[0, 10, 2][..], &[30, 4, 5][..]]);
+    }
+
+    #[test]
+    fn padding() {
+        let mut buffer = ImageBuffer::with_stride(2, 3, 4, 9u8).unwrap();
+        assert_eq!(buffer.as_slice().len(), 12);
+
+        for element in buffer.iter_mut() {
+            *element = 1;
+        }
+
+        assert_eq!(buffer.iter().count(), 6);
+        assert_eq!(buffer.row(2), &[1, 1]);
+        assert_eq!(buffer.as_slice(), &[1, 1, 9, 9, 1, 1, 9, 9, 1, 1, 9, 9]);
+    }
+
+    #[test]
+    fn too_big() {
+        let too_big = |result: Result<ImageBuffer<u64>>| match result {
+            Err(Error::DimensionsTooLarge { .. }) => {}
+            other => panic!("{:?}", other.map(|buffer| buffer.dimensions())),
+        };
+
+        too_big(ImageBuffer::with_stride(1, u32::MAX, usize::MAX / 2, 0));
+        too_big(ImageBuffer::new(u32::MAX, u32::MAX, 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn stride_too_small() {
+        let _ = ImageBuffer::with_stride(4, 1, 3, 0u8);
+    }
+
+    #[test]
+    fn rgba_bytes() {
+        let mut buffer = ImageBuffer::new(2, 2, Pixel::<u8>::default()).unwrap();
+        buffer[(1, 0)] = Pixel::new(1, 2, 3);
+        buffer[(0, 1)] = Pixel::new_rgba(4, 5, 6, 7);
+
+        assert_eq!(
+            buffer.as_rgba_bytes(),
+            &[0, 0, 0, 255, 1, 2, 3, 255, 4, 5, 6, 7, 0, 0, 0, 255]
+        );
+        assert_eq!(buffer.row_rgba_bytes(1), &[4, 5, 6, 7, 0, 0, 0, 255]);
+
+        buffer.as_rgba_bytes_mut()[0] = 8;
+        assert_eq!(buffer[(0, 0)], Pixel::new(8, 0, 0));
+    }
+}
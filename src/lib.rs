@@ -10,19 +10,39 @@
 //! all in the [`mandelbrot`](mandelbrot/index.html) module.
 //!
 //! If you're trying to get a little bit deeper:
+//!   - the [`big_float`](big_float/index.html) module has an arbitrary
+//!     precision floating point type, for coordinates `f64`s can't hold
 //!   - the [`color_scale`](color_scale/index.html) module has everything
 //!     related to coloring in the Mandelbrot Set
 //!   - the [`complex_number`](complex_number/index.html) module has the
 //!     implementations for the operators we use on `ComplexNumber` values in
 //!     this library
-//!   - and finally, the [`pixel`](pixel/index.html) module has everything
-//!     relevant to pixels in this library
+//!   - the [`error`](error/index.html) module has the `Error` type that
+//!     everything that can be given bad input returns
+//!   - the [`expression`](expression/index.html) module has a parser for
+//!     formulas typed in as text (like `z^3 - z + c`)
+//!   - the [`formula`](formula/index.html) module has the formulas that can
+//!     be iterated instead of the usual `z² + c` (Burning Ship, Tricorn, etc.)
+//!   - the [`gradient`](gradient/index.html) module has the `Gradient`
+//!     type, for palettes made of color stops
+//!   - the [`histogram`](histogram/index.html) module has the `Histogram`
+//!     type for coloring points by how their iteration counts rank
+//!   - the [`image_buffer`](image_buffer/index.html) module has the flat
+//!     buffer type pixels (and orbits) are stored in
+//!   - the [`perturbation`](perturbation/index.html) module has the
+//!     machinery for rendering deep zooms
+//!   - the [`palette`](palette/index.html) module has loaders for palette
+//!     files from other programs (Fractint, Ultra Fractal and GIMP)
+//!   - the [`pixel`](pixel/index.html) module has everything relevant to
+//!     pixels in this library
+//!   - and finally, the [`sampling`](sampling/index.html) module has the
+//!     supersampling patterns (for anti-aliasing)
 //!
 //! ## Using the `Mandelbrot` Type:
 //! Here's how it's meant to be used:
 //! ```rust
 //! use mandelbrot::{
-//!     color_scale::{ColorScale, SimpleColorScale},
+//!     color_scale::SimpleColorScale,
 //!     complex_number::ComplexNumber,
 //!     mandelbrot::{Mandelbrot, MandelbrotConfig, Viewport},
 //! };
@@ -32,70 +52,79 @@
 //!
 //! // Then you make a Viewport:
 //! // This tells the generator what part of the Mandelbrot Set you actually
-//! // want to draw.
+//! // want to draw. You can give it a top left corner and a size, a center
+//! // and a zoom (`Viewport::from_center_zoom`) or two corners
+//! // (`Viewport::from_corners`); viewports can also be panned, zoomed and
+//! // rotated after the fact.
 //!
-//! let viewport = Viewport::<f64> {
-//!     top_left: ComplexNumber::new(-3.0, 1.15),
-//!     width: 4f64,
-//!     height: 2.25,
-//! };
+//! let viewport = Viewport::new(ComplexNumber::new(-3.0, 1.15), 4f64, 2.25);
 //!
 //! // Next, you can make a MandelbrotConfig struct:
 //! // This includes the Viewport and the dimensions we picked and also a
-//! // Coloring Function. The Coloring Function determines how we color in
-//! // our Mandelbrot Set.
+//! // Color Scale. The Color Scale determines how we color in our
+//! // Mandelbrot Set (any closure that takes an `Orbit` and a `RenderInfo`
+//! // works too).
+//! // Everything else (like the number of threads to use) gets a default
+//! // value that you can change afterwards.
 //!
-//! let config = MandelbrotConfig::<u8> {
+//! let config = MandelbrotConfig::<u8>::new(
 //!     dimensions,
 //!     viewport,
-//!     color_fn: Box::new(SimpleColorScale::pixel_color),
-//! };
+//!     Box::new(SimpleColorScale {}),
+//! );
 //!
 //! // Now, we can create a Mandelbrot struct:
-//! let mut mandelbrot = Mandelbrot::new(config);
+//! // (This fails if the dimensions or the viewport don't make sense)
+//! let mut mandelbrot = Mandelbrot::new(config).unwrap();
 //!
 //! // And finally, we can run iterations:
 //! mandelbrot.run_iterations(50);
 //!
-//! // And get pixel data out of the struct:
+//! // And get pixel data out of the struct (`as_rgba_bytes` gives you bytes
+//! // that can go straight to an image encoder):
 //! let data = mandelbrot.get_pixels();
 //! ```
 //!
 //! [sample]: https://raw.githubusercontent.com/rrbutani/mandelbrot/master/tests/assets/FHD_50_s_cc_140_1_1.png
 
+pub mod big_float;
 pub mod color_scale;
 pub mod complex_number;
+pub mod error;
+pub mod expression;
+pub mod formula;
+pub mod gradient;
+pub mod histogram;
+pub mod image_buffer;
 pub mod mandelbrot;
+pub mod palette;
+pub mod perturbation;
 pub mod pixel;
+pub mod sampling;
+
+pub use error::{Error, Result};
 
 #[cfg(test)]
 mod tests {
 
-    use color_scale::{ColorScale, SimpleColorScale};
+    use color_scale::SimpleColorScale;
     use complex_number::ComplexNumber;
     use mandelbrot::{Mandelbrot, MandelbrotConfig, Viewport};
 
     #[test]
     fn altogether_now() {
         let dimensions = (1920, 1080);
-        let viewport = Viewport::<f64> {
-            top_left: ComplexNumber::new(-3.0, 1.15),
-            width: 4f64,
-            height: 2.25,
-        };
+        let viewport = Viewport::new(ComplexNumber::new(-3.0, 1.15), 4f64, 2.25);
 
         // We're going to use _really_ big pixels for fun!
-        let config = MandelbrotConfig::<u32> {
-            dimensions,
-            viewport,
-            color_fn: Box::new(SimpleColorScale::pixel_color),
-        };
+        let config =
+            MandelbrotConfig::<u32>::new(dimensions, viewport, Box::new(SimpleColorScale {}));
 
-        let mut mandelbrot = Mandelbrot::new(config);
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
         mandelbrot.run_iterations(50);
         let data = mandelbrot.get_pixels();
 
-        assert_eq!(data.len(), 1080);
-        assert_eq!(data[0].len(), 1920);
+        assert_eq!(data.dimensions(), (1920, 1080));
+        assert_eq!(data.row(0).len(), 1920);
     }
 }
@@ -3,13 +3,26 @@
 extern crate num_traits;
 
 use pixel::{Pixel, PixelMath};
-use std::fmt::{Debug, UpperHex};
+use std::fmt::{self, Debug, UpperHex};
 
-use self::num_traits::{sign::Unsigned, Bounded, Float, Zero};
+use self::num_traits::{sign::Unsigned, AsPrimitive, Bounded, Float, ToPrimitive, Zero};
 
-use std::{cmp, convert::From};
+use std::{
+    cmp, mem,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+};
 
+use color_scale::{ColorScale, GradientColorScale};
 use complex_number::ComplexNumber;
+use error::{Error, Result};
+use formula::{Formula, Multibrot};
+use gradient::{Gradient, Wrap};
+use histogram::Histogram;
+use image_buffer::ImageBuffer;
+use perturbation::{Perturbation, SeriesApproximation};
+use sampling::{self, Supersampling};
 
 /// This represents the section of the complex plane that we're going to
 /// calculate values (`ComplexNumber`) and generate pixels (`Pixel`) for.
@@ -17,6 +30,14 @@ use complex_number::ComplexNumber;
 /// `top_left` sets the coordinate to start at, width determines the real
 /// part values to cover, and height determines the imaginary part values to
 /// cover.
+///
+/// The grid can also be rotated (counterclockwise, by `rotation` radians)
+/// around its center; `top_left`, `width` and `height` describe the grid
+/// _before_ it's rotated.
+///
+/// The transforms (`zoom_at`, `pan`, `rotate`, etc.) all give back a new
+/// `Viewport`.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Viewport<T: Float> {
     /// The top left coordinate for the grid that is to be plotted
     pub top_left: ComplexNumber<T>,
@@ -24,6 +45,479 @@ pub struct Viewport<T: Float> {
     pub width: T,
     /// Height of the grid
     pub height: T,
+    /// How far the grid is rotated around its center, in radians
+    pub rotation: T,
+}
+
+/// How much of the complex plane a `Viewport` made with a zoom of 1 covers,
+/// across its width
+const BASE_WIDTH: f64 = 4.0;
+
+impl<T: Float> Viewport<T> {
+    /// Makes an (unrotated) viewport with the top left corner, width and
+    /// height given
+    pub fn new(top_left: ComplexNumber<T>, width: T, height: T) -> Viewport<T> {
+        Viewport {
+            top_left,
+            width,
+            height,
+            rotation: T::zero(),
+        }
+    }
+
+    /// Makes an (unrotated) viewport with the center, width and height given
+    pub fn from_center(center: ComplexNumber<T>, width: T, height: T) -> Viewport<T> {
+        let two = T::one() + T::one();
+        let offset = ComplexNumber::new(width / two, -height / two);
+
+        Self::new(center - offset, width, height)
+    }
+
+    /// Makes an (unrotated) viewport around `center` for an image with the
+    /// dimensions given
+    ///
+    /// At a zoom of 1 the viewport is 4 wide (enough to fit the whole
+    /// Mandelbrot Set); each doubling of the zoom halves that. The height is
+    /// picked to match the aspect ratio of `dimensions`.
+    pub fn from_center_zoom(
+        center: ComplexNumber<T>,
+        zoom: T,
+        dimensions: (u32, u32),
+    ) -> Viewport<T> {
+        let width = Self::cast(BASE_WIDTH) / zoom;
+        let (w, h) = dimensions;
+
+        Self::from_center(center, width, width * Self::cast(h) / Self::cast(w))
+    }
+
+    /// Makes an (unrotated) viewport with the two opposite corners given (in
+    /// any order)
+    pub fn from_corners(a: ComplexNumber<T>, b: ComplexNumber<T>) -> Viewport<T> {
+        let top_left = ComplexNumber::new(a.real().min(b.real()), a.imag().max(b.imag()));
+
+        Self::new(
+            top_left,
+            (a.real() - b.real()).abs(),
+            (a.imag() - b.imag()).abs(),
+        )
+    }
+
+    /// The point in the middle of the viewport
+    pub fn center(&self) -> ComplexNumber<T> {
+        let two = T::one() + T::one();
+
+        self.top_left + ComplexNumber::new(self.width / two, -self.height / two)
+    }
+
+    /// Works out where the top left pixel of an image with the dimensions
+    /// given lands, and the distances (in the complex plane) between
+    /// neighbouring pixels along a row and down a column
+    pub fn grid(
+        &self,
+        dimensions: (u32, u32),
+    ) -> (ComplexNumber<T>, ComplexNumber<T>, ComplexNumber<T>) {
+        let (w, h) = dimensions;
+        let zero = T::zero();
+
+        let rotation = ComplexNumber::new(self.rotation.cos(), self.rotation.sin());
+        let d_w = rotation * ComplexNumber::new(self.width / Self::cast(w), zero);
+        let d_h = rotation * ComplexNumber::new(zero, -self.height / Self::cast(h));
+
+        // Rotate the top left corner around the center (this is written so
+        // that without any rotation the corner comes out exactly as is):
+        let corner = self.top_left - self.center();
+        let unrotated = ComplexNumber::new(T::one(), zero);
+        let origin = self.top_left + (rotation - unrotated) * corner;
+
+        (origin, d_w, d_h)
+    }
+
+    /// The point in the complex plane the pixel given maps to in an image
+    /// with the dimensions given
+    pub fn point_at(&self, pixel: (u32, u32), dimensions: (u32, u32)) -> ComplexNumber<T> {
+        let (origin, d_w, d_h) = self.grid(dimensions);
+        let (x, y) = pixel;
+
+        origin + d_w * Self::cast(x) + d_h * Self::cast(y)
+    }
+
+    /// Grows the viewport (around its center) so that it has the same aspect
+    /// ratio as an image with the dimensions given
+    ///
+    /// Nothing that was in the viewport before gets cut off.
+    pub fn fit_to(&self, dimensions: (u32, u32)) -> Viewport<T> {
+        let (w, h) = dimensions;
+        let aspect = Self::cast(w) / Self::cast(h);
+
+        let (width, height) = if self.width / self.height < aspect {
+            (self.height * aspect, self.height)
+        } else {
+            (self.width, self.width / aspect)
+        };
+
+        self.with_size(width, height)
+    }
+
+    /// Zooms in by `factor` (or out, for factors less than 1), keeping the
+    /// point under the pixel given (in an image with the dimensions given)
+    /// where it is
+    pub fn zoom_at(&self, pixel: (u32, u32), factor: T, dimensions: (u32, u32)) -> Viewport<T> {
+        let point = self.point_at(pixel, dimensions);
+        let center = point + (self.center() - point) / factor;
+
+        Viewport {
+            rotation: self.rotation,
+            ..Self::from_center(center, self.width / factor, self.height / factor)
+        }
+    }
+
+    /// Moves the viewport `dx` to the right and `dy` up
+    ///
+    /// The distances are in the complex plane's units but are measured along
+    /// the viewport's own (possibly rotated) axes, so a pan to the right moves
+    /// the image to the left no matter how it's rotated.
+    pub fn pan(&self, dx: T, dy: T) -> Viewport<T> {
+        let rotation = ComplexNumber::new(self.rotation.cos(), self.rotation.sin());
+
+        Viewport {
+            top_left: self.top_left + rotation * ComplexNumber::new(dx, dy),
+            ..*self
+        }
+    }
+
+    /// Rotates the viewport (counterclockwise, around its center) by `angle`
+    /// radians
+    pub fn rotate(&self, angle: T) -> Viewport<T> {
+        Viewport {
+            rotation: self.rotation + angle,
+            ..*self
+        }
+    }
+
+    /// Checks that this viewport covers some area and that all its
+    /// coordinates are finite
+    pub fn validate(&self) -> Result<()> {
+        let (x, y) = (self.top_left.real(), self.top_left.imag());
+
+        if !x.is_finite() || !y.is_finite() {
+            Err(Error::InvalidViewport("the top left corner isn't finite"))
+        } else if !self.width.is_finite() || !self.height.is_finite() {
+            Err(Error::InvalidViewport("the width or height isn't finite"))
+        } else if !self.rotation.is_finite() {
+            Err(Error::InvalidViewport("the rotation isn't finite"))
+        } else if self.width <= T::zero() || self.height <= T::zero() {
+            Err(Error::InvalidViewport(
+                "the width and height have to be greater than 0",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resizes the viewport, keeping its center and rotation
+    fn with_size(&self, width: T, height: T) -> Viewport<T> {
+        Viewport {
+            rotation: self.rotation,
+            ..Self::from_center(self.center(), width, height)
+        }
+    }
+
+    fn cast<N: ToPrimitive>(n: N) -> T {
+        T::from(n).unwrap_or_else(T::nan)
+    }
+}
+
+impl<T: Float + fmt::Display> fmt::Display for Viewport<T> {
+    /// Writes the viewport as `top_left=<complex> width=<number>
+    /// height=<number>`, with ` rotation=<radians>` on the end if it's
+    /// rotated
+    ///
+    /// Without a precision, `f64` viewports are written with enough digits to
+    /// be parsed back exactly.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (top_left, width, height) = (self.top_left, self.width, self.height);
+
+        match f.precision() {
+            Some(p) => write!(
+                f,
+                "top_left={:.*} width={:.*} height={:.*}",
+                p, top_left, p, width, p, height
+            )?,
+            None => write!(f, "top_left={} width={} height={}", top_left, width, height)?,
+        }
+
+        match f.precision() {
+            _ if self.rotation.is_zero() => Ok(()),
+            Some(p) => write!(f, " rotation={:.*}", p, self.rotation),
+            None => write!(f, " rotation={}", self.rotation),
+        }
+    }
+}
+
+impl<T: Float + FromStr> FromStr for Viewport<T> {
+    type Err = Error;
+
+    /// Parses a viewport written as `key=value` pairs separated by spaces
+    ///
+    /// The keys are `top_left` (or `center`), `width`, `height` and
+    /// (optionally) `rotation`, in any order. Corners and centers can be
+    /// written in any of the forms `ComplexNumber` accepts, like
+    /// `center=(-0.5, 0) width=3 height=2`.
+    fn from_str(s: &str) -> Result<Viewport<T>> {
+        let error = || Error::Parse {
+            input: s.to_string(),
+            expected: "a viewport (like `top_left=-2.5+1.25i width=3.5 height=2.5`)",
+        };
+
+        // Pieces without an `=` belong to the value before them (complex
+        // numbers can have spaces in them):
+        let mut fields: Vec<(&str, String)> = Vec::new();
+        for piece in s.split_whitespace() {
+            match piece.find('=') {
+                Some(k) => fields.push((&piece[..k], piece[k + 1..].to_string())),
+                None => match fields.last_mut() {
+                    Some(&mut (_, ref mut value)) => {
+                        value.push(' ');
+                        value.push_str(piece);
+                    }
+                    None => return Err(error()),
+                },
+            }
+        }
+
+        let (mut corner, mut center, mut width, mut height, mut rotation) =
+            (None, None, None, None, None);
+
+        for (key, value) in fields {
+            let slot = match key {
+                "top_left" => {
+                    corner = Some(value.parse::<ComplexNumber<T>>().map_err(|_| error())?);
+                    continue;
+                }
+                "center" => {
+                    center = Some(value.parse::<ComplexNumber<T>>().map_err(|_| error())?);
+                    continue;
+                }
+                "width" => &mut width,
+                "height" => &mut height,
+                "rotation" => &mut rotation,
+                _ => return Err(error()),
+            };
+
+            *slot = Some(value.trim().parse::<T>().map_err(|_| error())?);
+        }
+
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(error()),
+        };
+
+        let viewport = match (corner, center) {
+            (Some(corner), None) => Viewport::new(corner, width, height),
+            (None, Some(center)) => Viewport::from_center(center, width, height),
+            _ => return Err(error()),
+        };
+
+        Ok(viewport.rotate(rotation.unwrap_or_else(T::zero)))
+    }
+}
+
+/// Everything we know about a single point after running iterations on it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    /// The number of iterations the point has been through; if the point
+    /// escaped, this is the number of iterations it took to escape
+    pub iterations: u32,
+    /// Where the point ended up
+    pub z: ComplexNumber<f64>,
+    /// The period of the cycle the orbit settled into, if it was found to be
+    /// stuck in one (which means the point is in the Mandelbrot Set)
+    pub period: Option<u32>,
+    /// The multiplier of that cycle: the derivative of the orbit with respect
+    /// to `z` over one trip around it (its magnitude is less than 1, since
+    /// only attracting cycles count)
+    pub multiplier: Option<ComplexNumber<f64>>,
+    /// An estimate of how far the point is from the edge of the set (in the
+    /// complex plane), if it settled into a cycle and distance estimation
+    /// was on; like the exterior estimate, the actual distance is between a
+    /// quarter of it and all of it
+    ///
+    /// This is only worked out for `z² + c` (and not in Julia mode).
+    pub interior_distance: Option<f64>,
+    /// The derivative of `z` with respect to `c` (or to the starting point,
+    /// in Julia mode); only tracked when distance estimation is on
+    pub dz: ComplexNumber<f64>,
+    /// The point in the orbit we're checking for repeats of (for periodicity
+    /// checking)
+    checkpoint: ComplexNumber<f64>,
+    /// The number of iterations since the checkpoint was taken
+    since_checkpoint: u32,
+    /// The number of iterations to go before moving the checkpoint
+    checkpoint_interval: u32,
+    /// The difference between this orbit and the reference orbit (only used
+    /// when rendering with perturbation)
+    delta: ComplexNumber<f64>,
+    /// Where in the reference orbit this orbit is (only used when rendering
+    /// with perturbation)
+    reference_index: usize,
+    /// Whether this is a sample adaptive supersampling hasn't needed (yet);
+    /// dormant orbits aren't iterated or colored
+    dormant: bool,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Orbit {
+            iterations: 0,
+            z: ComplexNumber::new(0.0, 0.0),
+            period: None,
+            multiplier: None,
+            interior_distance: None,
+            dz: ComplexNumber::new(0.0, 0.0),
+            checkpoint: ComplexNumber::new(0.0, 0.0),
+            since_checkpoint: 0,
+            checkpoint_interval: 1,
+            delta: ComplexNumber::new(0.0, 0.0),
+            reference_index: 0,
+            dormant: false,
+        }
+    }
+}
+
+impl Orbit {
+    /// An estimate of how far the point is from the set (in the complex
+    /// plane), if distance estimation was on and the point escaped
+    ///
+    /// The estimate is `d|z| ln|z| / |dz|`, where `d` is the degree of the
+    /// formula (`RenderInfo::degree`; 2 for the usual Mandelbrot Set); the
+    /// actual distance is at least a quarter of it and, away from cusps, not
+    /// much more than it.
+    /// It's only meaningful for points that escaped (which is up to the
+    /// caller to check) and it gets more accurate with bigger escape radii.
+    pub fn distance_estimate(&self, degree: f64) -> Option<f64> {
+        let r = self.z.abs();
+        let estimate = degree * r * r.ln() / self.dz.abs();
+
+        if estimate.is_finite() && estimate > 0.0 {
+            Some(estimate)
+        } else {
+            None
+        }
+    }
+
+    /// Moves the periodicity checkpoint along and checks whether the orbit
+    /// has come back around to it (see `iterate_coordinate`)
+    ///
+    /// If it has, the period and the multiplier are recorded and this returns
+    /// true.
+    fn check_periodicity<F: Formula + ?Sized>(
+        &mut self,
+        c: ComplexNumber<f64>,
+        formula: &F,
+        tolerance_sqr: f64,
+    ) -> bool {
+        self.since_checkpoint += 1;
+
+        if (self.z - self.checkpoint).norm_sqr() < tolerance_sqr {
+            if let Some(multiplier) = cycle_multiplier(self.z, c, self.since_checkpoint, formula) {
+                self.period = Some(self.since_checkpoint);
+                self.multiplier = Some(multiplier);
+                return true;
+            }
+        }
+
+        if self.since_checkpoint == self.checkpoint_interval {
+            self.checkpoint = self.z;
+            self.since_checkpoint = 0;
+            self.checkpoint_interval =
+                cmp::min(self.checkpoint_interval * 2, MAX_CHECKPOINT_INTERVAL);
+        }
+
+        false
+    }
+
+    /// Moves this orbit back to the start of the reference orbit (where the
+    /// reference orbit is 0), which makes the current value the new delta
+    fn rebase(&mut self) {
+        self.delta = self.z;
+        self.reference_index = 0;
+    }
+}
+
+/// Information about the render as a whole that color scales get along with
+/// each point's `Orbit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderInfo<'a> {
+    /// The largest number of iterations any point has been through (a point
+    /// with this many iterations is considered to be in the Mandelbrot Set)
+    pub max_iterations: u32,
+    /// The escape radius that was used
+    pub escape_radius: f64,
+    /// The degree of the formula that was used (see `Formula::degree`)
+    pub degree: f64,
+    /// The distance between neighbouring pixels in the complex plane (for
+    /// turning distance estimates into pixels)
+    pub pixel_size: f64,
+    /// How the iteration counts of the points that escaped are distributed,
+    /// if `MandelbrotConfig::histogram` is on
+    pub histogram: Option<&'a Histogram>,
+    /// Statistics about all the points in the frame
+    pub stats: FrameStats,
+}
+
+/// Statistics about the points in a frame, gathered (in a pass over the
+/// orbits) each time iterations are run, before the pixels are colored
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameStats {
+    /// The number of points in the frame (with supersampling, the number of
+    /// samples)
+    pub points: u64,
+    /// The number of points that escaped
+    pub escaped: u64,
+    /// The fewest iterations any point took to escape (0 if none did)
+    pub min_escape_iterations: u32,
+    /// The most iterations any point took to escape (0 if none did)
+    pub max_escape_iterations: u32,
+}
+
+impl FrameStats {
+    /// Tallies up the orbits given; the ones with fewer than
+    /// `max_iterations` iterations count as having escaped
+    pub fn new<'a, I: IntoIterator<Item = &'a Orbit>>(
+        orbits: I,
+        max_iterations: u32,
+    ) -> FrameStats {
+        let mut stats = FrameStats::default();
+        let mut range: Option<(u32, u32)> = None;
+
+        for orbit in orbits {
+            stats.points += 1;
+
+            if orbit.iterations < max_iterations {
+                stats.escaped += 1;
+                range = Some(match range {
+                    Some((min, max)) => (min.min(orbit.iterations), max.max(orbit.iterations)),
+                    None => (orbit.iterations, orbit.iterations),
+                });
+            }
+        }
+
+        if let Some((min, max)) = range {
+            stats.min_escape_iterations = min;
+            stats.max_escape_iterations = max;
+        }
+
+        stats
+    }
+
+    /// The fraction of the points that escaped (0 for an empty frame)
+    pub fn escaped_fraction(&self) -> f64 {
+        if self.points == 0 {
+            0.0
+        } else {
+            self.escaped as f64 / self.points as f64
+        }
+    }
 }
 
 /// This struct provides all the settings needed for the `Mandelbrot` type to go
@@ -31,110 +525,2866 @@ pub struct Viewport<T: Float> {
 ///
 /// Ultimately, based on the dimensions and the `Viewport` provided, discretized
 /// coordinates are calculated for each pixel and (after iterations are run) the
-/// coloring function given is used to color in each pixel.
+/// color scale given is used to color in each pixel.
 pub struct MandelbrotConfig<P: Unsigned + Bounded + UpperHex + Copy + Zero> {
     /// The pixel dimensions of the area to generate values/pixels for
     pub dimensions: (u32, u32),
     /// The `Viewport` to cover
     pub viewport: Viewport<f64>,
-    /// The (Boxed) coloring function to be used
-    pub color_fn: Box<Fn(u32, ComplexNumber<f64>, u32) -> Pixel<P>>,
+    /// The (Boxed) color scale pixels are colored in with (closures that
+    /// take an `Orbit` and a `RenderInfo` work too)
+    pub color_fn: Box<dyn ColorScale<P>>,
+    /// The number of threads to run iterations on
+    ///
+    /// Rows are handed out to the threads in bands; the output doesn't depend
+    /// on how many threads are used. `0` is treated the same as `1`.
+    pub threads: usize,
+    /// How far from the origin a point has to get before it's considered to
+    /// have escaped
+    ///
+    /// The usual value is 2, but smooth coloring looks a lot better with big
+    /// escape radii (like 256 or even 1e10).
+    pub escape_radius: f64,
+    /// Whether to skip points inside the main cardioid or the period-2 bulb
+    ///
+    /// Points in either region never escape, so when this is set they're
+    /// detected up front (no iterations needed) and treated as if they ran
+    /// all their iterations without escaping. The pixels that come out are
+    /// the same either way, but the `ending_point` handed to the coloring
+    /// function for these points is left at the origin. These points get a
+    /// period of 1 (cardioid) or 2 (bulb).
+    pub interior_check: bool,
+    /// Whether to look for orbits that have settled into a cycle
+    ///
+    /// Points whose orbits repeat themselves (Brent's algorithm is used to find
+    /// the cycles) are in the Mandelbrot Set, so they're treated as if they ran
+    /// all their iterations without escaping and the period of the cycle is
+    /// recorded in their `Orbit`.
+    pub periodicity_check: bool,
+    /// Settings for perturbation rendering, for deep zooms
+    ///
+    /// When this is set, the `Viewport` is relative to the reference point
+    /// and the cardioid/bulb check is skipped (`f64`s aren't precise enough to
+    /// say which side of the boundary a deep zoom pixel is on). See the
+    /// [`perturbation`](../perturbation/index.html) module for details.
+    pub perturbation: Option<Perturbation>,
+    /// Renders the Julia Set for this `c` instead of the Mandelbrot Set
+    ///
+    /// Every pixel uses this `c` and starts its orbit at its own coordinate
+    /// (`z0`) instead of at 0. The cardioid/bulb check is skipped (it only
+    /// makes sense for the Mandelbrot Set) and perturbation rendering isn't
+    /// supported. See `Mandelbrot::julia_config` for an easy way to make one
+    /// of these.
+    pub julia: Option<ComplexNumber<f64>>,
+    /// The formula to iterate
+    ///
+    /// The default is the usual `z² + c`. The cardioid/bulb check is only
+    /// done and perturbation rendering is only supported for that formula
+    /// (see `Formula::is_quadratic`). The formula is shared (not boxed) so
+    /// that `Mandelbrot::julia_config` can hand it on.
+    pub formula: Arc<dyn Formula>,
+    /// Whether to track the derivative of each orbit (`Orbit::dz`) so that
+    /// coloring functions can use `Orbit::distance_estimate` (and, for points
+    /// that settle into a cycle, `Orbit::interior_distance`)
+    ///
+    /// This makes iterations a bit slower, so it's off by default. It only
+    /// works for formulas that have a derivative (see `Formula::derivative`);
+    /// orbits for other formulas end up without a distance estimate. With
+    /// perturbation, the series approximation is held to the square of its
+    /// usual tolerance so it skips fewer iterations.
+    pub distance_estimation: bool,
+    /// Where to take samples within each pixel (see `Supersampling`); the
+    /// colors of a pixel's samples are averaged in linear light
+    ///
+    /// Each pixel gets one sample, at the pixel's point, by default.
+    pub supersampling: Supersampling,
+    /// If set, only pixels that stand out from their neighbours get
+    /// supersampled: every pixel gets a sample at its point first and pixels
+    /// whose color differs from one of their neighbours' by more than this
+    /// (as a fraction of the range of a color channel, so between 0 and 1)
+    /// get the rest of their samples
+    ///
+    /// Pixels that have been supersampled stay that way in later calls to
+    /// `run_iterations`.
+    pub adaptive_threshold: Option<f64>,
+    /// Whether to count up how many points escaped at each iteration count
+    /// (in a second pass over the orbits, once the iterations are done) so
+    /// that coloring functions can color by rank (see `Histogram` and
+    /// `HistogramColorScale`)
+    ///
+    /// This is off by default.
+    pub histogram: bool,
+}
+
+impl<P: Unsigned + Bounded + UpperHex + Copy + Zero> MandelbrotConfig<P> {
+    /// Makes a config with the given dimensions, `Viewport` and coloring
+    /// function; everything else is set to its default value.
+    ///
+    /// By default, iterations are spread across as many threads as there are
+    /// CPUs available, the escape radius is 2 and both the cardioid/bulb check
+    /// and the periodicity check are turned on. Perturbation rendering and
+    /// Julia mode are off, the formula is `z² + c` and distance estimation is
+    /// off.
+    pub fn new(
+        dimensions: (u32, u32),
+        viewport: Viewport<f64>,
+        color_fn: Box<dyn ColorScale<P>>,
+    ) -> MandelbrotConfig<P> {
+        MandelbrotConfig {
+            dimensions,
+            viewport,
+            color_fn,
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            escape_radius: 2.0,
+            interior_check: true,
+            periodicity_check: true,
+            perturbation: None,
+            julia: None,
+            formula: Arc::new(Multibrot::default()),
+            distance_estimation: false,
+            supersampling: Supersampling::None,
+            adaptive_threshold: None,
+            histogram: false,
+        }
+    }
+
+    /// The adaptive supersampling threshold, if adaptive supersampling is on
+    /// (it doesn't do anything with one sample per pixel)
+    fn adaptive(&self) -> Option<f64> {
+        self.adaptive_threshold
+            .filter(|_| self.supersampling.samples() > 1)
+    }
+
+    /// The number of `Orbit`s each pixel gets: one per sample, plus one at
+    /// the pixel's point for adaptive supersampling
+    fn samples_per_pixel(&self) -> usize {
+        match self.adaptive() {
+            Some(_) => self.supersampling.samples() + 1,
+            None => self.supersampling.samples(),
+        }
+    }
 }
 
 pub struct Mandelbrot<P: Unsigned + Bounded + UpperHex + Copy + Zero> {
     config: MandelbrotConfig<P>,
-    pixels: Vec<Vec<Pixel<P>>>,
-    values: Vec<Vec<(u32, ComplexNumber<f64>)>>,
+    pixels: ImageBuffer<Pixel<P>>,
+    values: ImageBuffer<Orbit>,
+    origin: ComplexNumber<f64>,
     steps: (ComplexNumber<f64>, ComplexNumber<f64>),
     iterations: u32,
+    /// The most iterations any point has been through (what coloring treats
+    /// as being in the set)
+    max_iterations: u32,
 }
 
-impl<P: 'static + Unsigned + Bounded + UpperHex + Copy + Zero + Into<f64>> Mandelbrot<P> {
-    pub fn new(config: MandelbrotConfig<P>) -> Mandelbrot<P> {
+impl<P: 'static + Unsigned + Bounded + UpperHex + Copy + Zero + Into<f64>> Mandelbrot<P>
+where
+    f64: AsPrimitive<P>,
+{
+    /// Sets up a `Mandelbrot` for the config given
+    ///
+    /// Fails if the dimensions have no pixels in them (or too many to
+    /// allocate), if the `Viewport` is degenerate (see `Viewport::validate`),
+    /// if the supersampling settings don't make sense or if perturbation
+    /// rendering is turned on along with Julia mode or with a formula other
+    /// than `z² + c`.
+    pub fn new(config: MandelbrotConfig<P>) -> Result<Mandelbrot<P>> {
         let (w, h) = config.dimensions;
 
-        let w_c = ComplexNumber::new(config.viewport.width, 0.0);
-        let h_c = ComplexNumber::new(0.0, -config.viewport.height);
+        if w == 0 || h == 0 {
+            return Err(Error::ZeroDimensions {
+                width: w,
+                height: h,
+            });
+        }
+
+        config.supersampling.validate()?;
+
+        match config.adaptive_threshold {
+            Some(threshold) if !(0.0..=1.0).contains(&threshold) => {
+                return Err(Error::InvalidConfig(
+                    "the adaptive supersampling threshold has to be between 0 and 1",
+                ));
+            }
+            _ => {}
+        }
+
+        // Each pixel's samples sit side by side in a row of `values`:
+        let samples = config.samples_per_pixel();
+        let largest = cmp::max(mem::size_of::<Orbit>(), mem::size_of::<Pixel<P>>());
+        (w as usize)
+            .checked_mul(samples)
+            .filter(|n| *n <= u32::MAX as usize)
+            .and_then(|n| n.checked_mul(h as usize))
+            .and_then(|n| n.checked_mul(largest))
+            .filter(|bytes| *bytes <= isize::MAX as usize)
+            .ok_or(Error::DimensionsTooLarge {
+                width: w,
+                height: h,
+            })?;
 
-        Mandelbrot {
+        config.viewport.validate()?;
+
+        if config.julia.is_some() && config.perturbation.is_some() {
+            return Err(Error::InvalidConfig(
+                "perturbation rendering isn't supported in Julia mode",
+            ));
+        }
+
+        if config.perturbation.is_some() && !config.formula.is_quadratic() {
+            return Err(Error::InvalidConfig(
+                "perturbation rendering only supports z² + c",
+            ));
+        }
+
+        // (This is where the viewport's rotation comes in)
+        let (origin, d_w, d_h) = config.viewport.grid(config.dimensions);
+
+        let mut values = ImageBuffer::new(w * samples as u32, h, Orbit::default())?;
+        if config.adaptive().is_some() {
+            put_samples_to_sleep(&mut values, samples);
+        }
+
+        Ok(Mandelbrot {
             config,
-            pixels: vec![vec![Pixel::<P>::default(); w as usize]; h as usize],
-            values: vec![vec![(0, ComplexNumber::new(0.0, 0.0)); w as usize]; h as usize],
-            steps: (w_c / w, h_c / h),
+            pixels: ImageBuffer::new(w, h, Pixel::<P>::default())?,
+            values,
+            origin,
+            steps: (d_w, d_h),
             iterations: 0,
+            max_iterations: 0,
+        })
+    }
+
+    /// Makes a config for the Julia Set that goes with the point under the
+    /// pixel given, with the coloring function given
+    ///
+    /// The config uses the same dimensions, thread count, escape radius,
+    /// periodicity check, distance estimation, supersampling and histogram
+    /// settings and formula as this `Mandelbrot`'s config and a viewport that
+    /// fits the whole Julia Set (which is always within 2 of the origin).
+    ///
+    /// Fails if this `Mandelbrot` is rendering with perturbation (its
+    /// viewport doesn't say where pixels are in the complex plane).
+    pub fn julia_config(
+        &self,
+        pixel: (u32, u32),
+        color_fn: Box<dyn ColorScale<P>>,
+    ) -> Result<MandelbrotConfig<P>> {
+        if self.config.perturbation.is_some() {
+            return Err(Error::InvalidConfig(
+                "can't pick points on a render that uses perturbation",
+            ));
         }
+
+        let dimensions = self.config.dimensions;
+        let (x, y) = pixel;
+        let c = self.origin + self.steps.0 * f64::from(x) + self.steps.1 * f64::from(y);
+
+        let viewport =
+            Viewport::from_center(ComplexNumber::new(0.0, 0.0), 4.0, 4.0).fit_to(dimensions);
+
+        Ok(MandelbrotConfig {
+            threads: self.config.threads,
+            escape_radius: self.config.escape_radius,
+            periodicity_check: self.config.periodicity_check,
+            julia: Some(c),
+            formula: self.config.formula.clone(),
+            distance_estimation: self.config.distance_estimation,
+            supersampling: self.config.supersampling,
+            adaptive_threshold: self.config.adaptive_threshold,
+            histogram: self.config.histogram,
+            ..MandelbrotConfig::new(dimensions, viewport, color_fn)
+        })
     }
 
     /// Returns a reference to the current state of the Pixels in the
     /// Mandelbrot Set
-    pub fn get_pixels(&self) -> &Vec<Vec<Pixel<P>>> {
+    pub fn get_pixels(&self) -> &ImageBuffer<Pixel<P>> {
         &self.pixels
     }
 
+    /// Returns a reference to the current state of the `Orbit` of each point
+    /// in the Mandelbrot Set
+    ///
+    /// With supersampling, each pixel has an `Orbit` for each of its samples
+    /// and they sit side by side in its row (so rows are a few times wider
+    /// than the image). For adaptive supersampling, the first one is at the
+    /// pixel's point and the rest are only meaningful for pixels that were
+    /// supersampled.
+    pub fn get_orbits(&self) -> &ImageBuffer<Orbit> {
+        &self.values
+    }
+
     /// Runs the number of iterations given across all the Pixels in the
     /// Mandelbrot Set
+    ///
+    /// The rows of the grid are split into bands which are iterated on
+    /// `config.threads` threads.
+    ///
+    /// With adaptive supersampling, this happens in two steps: the samples
+    /// at each pixel's point (and the samples of pixels that were
+    /// supersampled before) get iterated first, and then the pixels that
+    /// need it get the rest of their samples, which are brought up to the
+    /// same number of iterations as everything else.
     pub fn run_iterations(&mut self, num_iters: u32) {
-        let (w, h) = self.config.dimensions;
-        let (d_w, d_h) = self.steps;
-        let coordinate = self.config.viewport.top_left;
+        let threads = cmp::max(1, self.config.threads);
+        let escape_radius = self.config.escape_radius;
+        let escape_radius_sqr = escape_radius * escape_radius;
 
+        let first_pass = self.iterations == 0;
         self.iterations += num_iters;
 
-        let mut max_iterations: u32 = 0;
+        if let Some(ref mut perturbation) = self.config.perturbation {
+            perturbation
+                .reference
+                .extend(self.iterations as usize + 1, escape_radius_sqr);
+        }
 
-        for r in 0..(h as usize) {
-            for c in 0..(w as usize) {
-                self.values[r][c] = iterate_coordinate(
-                    self.values[r][c],
-                    coordinate + d_w * (c as f64) + d_h * (r as f64),
-                    num_iters,
-                );
+        // Every orbit is still at its start on the first pass, so that's when
+        // we can use series approximation to skip ahead:
+        let series = match self.config.perturbation {
+            Some(ref perturbation) if first_pass => {
+                let (d_w, d_h) = self.steps;
+                let (w, h) = self.config.dimensions;
+                let origin = self.origin;
+                let (right, bottom) = (d_w * f64::from(w), d_h * f64::from(h));
+
+                let radius = [
+                    origin,
+                    origin + right,
+                    origin + bottom,
+                    origin + right + bottom,
+                ]
+                .iter()
+                .map(|corner| corner.abs())
+                .fold(0.0, f64::max);
+
+                // (Samples can be up to half a pixel past the pixels)
+                let radius = if self.supersampled() {
+                    radius + (d_w.abs() + d_h.abs()) / 2.0
+                } else {
+                    radius
+                };
 
-                max_iterations = cmp::max(max_iterations, self.values[r][c].0);
+                // The series' derivative is a lot less accurate than the
+                // series itself, so distance estimates need a tighter bound:
+                let tolerance = if self.config.distance_estimation {
+                    perturbation.series_tolerance.powi(2)
+                } else {
+                    perturbation.series_tolerance
+                };
+
+                Some(SeriesApproximation::new(
+                    perturbation.reference.orbit(),
+                    perturbation.series_terms,
+                    radius,
+                    d_w.abs().min(d_h.abs()),
+                    tolerance,
+                    escape_radius,
+                    num_iters as usize,
+                ))
             }
-        }
+            _ => None,
+        };
+
+        let params = IterationParams {
+            origin: self.origin,
+            steps: self.steps,
+            width: self.values.width() as usize,
+            stride: self.values.stride(),
+            limit: num_iters,
+            total: self.iterations,
+            samples: self.config.samples_per_pixel(),
+            supersampling: self.config.supersampling,
+            centered: self.config.adaptive().is_some(),
+            escape_radius_sqr,
+            interior_check: self.config.interior_check
+                && self.config.formula.is_quadratic()
+                && self.config.perturbation.is_none()
+                && self.config.julia.is_none(),
+            periodicity_check: self.config.periodicity_check,
+            periodicity_tolerance_sqr: periodicity_tolerance_sqr(self.steps.0.abs()),
+            reference: self
+                .config
+                .perturbation
+                .as_ref()
+                .map(|p| p.reference.orbit()),
+            julia: self.config.julia,
+            formula: &*self.config.formula,
+            derivative: match (self.config.distance_estimation, self.config.julia) {
+                (false, _) => Derivative::Off,
+                (true, None) => Derivative::C,
+                (true, Some(_)) => Derivative::Z0,
+            },
+            glitch_tolerance_sqr: self
+                .config
+                .perturbation
+                .as_ref()
+                .map_or(0.0, |p| p.glitch_tolerance * p.glitch_tolerance),
+            series: series.as_ref(),
+        };
+
+        let mut max_iterations = iterate_all(&mut self.values, &params, threads);
 
-        for (r, row) in self.values.iter().enumerate() {
-            for (c, (iters, zn)) in row.iter().enumerate() {
-                self.pixels[r][c] = (self.config.color_fn)(*iters, *zn, max_iterations);
+        if let Some(threshold) = self.config.adaptive() {
+            let histogram = self.histogram(max_iterations);
+            let info = self.render_info(max_iterations, histogram.as_ref());
+            let color_fn = &self.config.color_fn;
+
+            if wake_samples(&mut self.values, params.samples, threshold, |orbit| {
+                color_fn.pixel_color(orbit, &info)
+            }) {
+                // The samples that just woke up have never been iterated, so
+                // they get all the iterations (see `iterate_band`) while
+                // everything else gets none:
+                let catch_up = IterationParams {
+                    limit: 0,
+                    series: None,
+                    ..params
+                };
+
+                max_iterations = cmp::max(
+                    max_iterations,
+                    iterate_all(&mut self.values, &catch_up, threads),
+                );
             }
         }
+
+        self.max_iterations = max_iterations;
+        self.recolor();
     }
 
-    pub fn reset(&mut self) {
-        self.pixels
-            .iter_mut()
-            .map(|col| col.iter_mut().map(|px| *px = Pixel::<P>::default()))
-            .count();
-        self.values
-            .iter_mut()
-            .map(|row| {
-                row.iter_mut()
-                    .map(|coor| *coor = (0, ComplexNumber::new(0.0, 0.0)))
+    /// Colors the pixels in again from the orbits the last `run_iterations`
+    /// left behind, without running any iterations
+    pub fn recolor(&mut self) {
+        let histogram = self.histogram(self.max_iterations);
+        let info = self.render_info(self.max_iterations, histogram.as_ref());
+        let samples = self.config.samples_per_pixel();
+
+        color_pixels(
+            &mut self.pixels,
+            &self.values,
+            samples,
+            &*self.config.color_fn,
+            &info,
+        );
+    }
+
+    /// Swaps in a new color scale and recolors the pixels with it (without
+    /// running any iterations)
+    pub fn set_color_fn(&mut self, color_fn: Box<dyn ColorScale<P>>) {
+        self.config.color_fn = color_fn;
+        self.recolor();
+    }
+
+    /// Colors the orbits the last `run_iterations` left behind with the color
+    /// scale given, into a new image (this `Mandelbrot`'s pixels are left as
+    /// they are)
+    pub fn render_with(&self, color_scale: &dyn ColorScale<P>) -> ImageBuffer<Pixel<P>> {
+        let histogram = self.histogram(self.max_iterations);
+        let info = self.render_info(self.max_iterations, histogram.as_ref());
+
+        let mut pixels = self.pixels.clone();
+        color_pixels(
+            &mut pixels,
+            &self.values,
+            self.config.samples_per_pixel(),
+            color_scale,
+            &info,
+        );
+
+        pixels
+    }
+
+    /// Makes `frames` frames of palette cycling from the orbits the last
+    /// `run_iterations` left behind: each frame is colored with a
+    /// `GradientColorScale` whose gradient is shifted along a little further
+    /// than the last one's, so that over all the frames the colors go around
+    /// once (and the frame after the last one would be the first one again)
+    ///
+    /// Gradients that repeat go around once every 1 of offset and gradients
+    /// that mirror every 2; gradients that clamp don't go around at all, so
+    /// their colors just slide along.
+    pub fn cycle_colors(&self, gradient: &Gradient, frames: u32) -> Vec<ImageBuffer<Pixel<P>>>
+    where
+        f64: From<P>,
+    {
+        let histogram = self.histogram(self.max_iterations);
+        let info = self.render_info(self.max_iterations, histogram.as_ref());
+        let period = match gradient.wrap {
+            Wrap::Mirror => 2.0,
+            Wrap::Repeat | Wrap::Clamp => 1.0,
+        };
+
+        let mut scale = GradientColorScale::new(gradient.clone());
+        (0..frames)
+            .map(|frame| {
+                scale.gradient.offset = gradient.offset + period * frame as f64 / frames as f64;
+
+                let mut pixels = self.pixels.clone();
+                color_pixels(
+                    &mut pixels,
+                    &self.values,
+                    self.config.samples_per_pixel(),
+                    &scale,
+                    &info,
+                );
+
+                pixels
             })
-            .count();
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.pixels.fill(Pixel::<P>::default());
+        self.values.fill(Orbit::default());
         self.iterations = 0;
+        self.max_iterations = 0;
+
+        if self.config.adaptive().is_some() {
+            put_samples_to_sleep(&mut self.values, self.config.samples_per_pixel());
+        }
+    }
+
+    /// Counts up the orbits by iteration count, if the config asks for it
+    fn histogram(&self, max_iterations: u32) -> Option<Histogram> {
+        if self.config.histogram {
+            let orbits = self.values.iter().filter(|orbit| !orbit.dormant);
+            Some(Histogram::new(orbits, max_iterations))
+        } else {
+            None
+        }
+    }
+
+    fn render_info<'h>(
+        &self,
+        max_iterations: u32,
+        histogram: Option<&'h Histogram>,
+    ) -> RenderInfo<'h> {
+        RenderInfo {
+            max_iterations,
+            escape_radius: self.config.escape_radius,
+            degree: self.config.formula.degree(),
+            pixel_size: self.steps.0.abs(),
+            histogram,
+            stats: FrameStats::new(
+                self.values.iter().filter(|orbit| !orbit.dormant),
+                max_iterations,
+            ),
+        }
+    }
+
+    /// Whether pixels get more than one sample
+    fn supersampled(&self) -> bool {
+        self.config.samples_per_pixel() > 1
     }
 }
 
-/// A helper function that runs the number of iterations given on a single
-/// coordinate
-fn iterate_coordinate<T: Float + Debug>(
-    current_coord: (u32, ComplexNumber<T>),
-    c: ComplexNumber<T>,
-    limit: u32,
-) -> (u32, ComplexNumber<T>)
+/// Colors in each pixel from its orbits (averaging the colors of its samples
+/// that are awake, with supersampling)
+fn color_pixels<P>(
+    pixels: &mut ImageBuffer<Pixel<P>>,
+    values: &ImageBuffer<Orbit>,
+    samples: usize,
+    color_scale: &dyn ColorScale<P>,
+    info: &RenderInfo,
+) where
+    P: 'static + Unsigned + Bounded + UpperHex + Copy + Zero + Into<f64>,
+    f64: AsPrimitive<P>,
+{
+    let mut colors = Vec::with_capacity(samples);
+
+    for (pixels, orbits) in pixels.rows_mut().zip(values.rows()) {
+        for (pixel, orbits) in pixels.iter_mut().zip(orbits.chunks(samples)) {
+            *pixel = if samples == 1 {
+                color_scale.pixel_color(&orbits[0], info)
+            } else {
+                colors.clear();
+                colors.extend(
+                    orbits
+                        .iter()
+                        .filter(|orbit| !orbit.dormant)
+                        .map(|orbit| color_scale.pixel_color(orbit, info)),
+                );
+
+                sampling::average(&colors)
+            };
+        }
+    }
+}
+
+/// Runs `iterate_band` on all of `values`, on the number of threads given
+///
+/// Returns the largest iteration count.
+fn iterate_all(values: &mut ImageBuffer<Orbit>, params: &IterationParams, threads: usize) -> u32 {
+    if threads == 1 {
+        return iterate_band(values.as_mut_slice(), 0, params);
+    }
+
+    // Hand out a few bands per thread so that threads that get the cheap rows
+    // (far from the set) don't end up sitting idle:
+    let band_rows = cmp::max(1, values.height() as usize / (threads * BANDS_PER_THREAD));
+    let bands = Mutex::new(
+        values
+            .as_mut_slice()
+            .chunks_mut(band_rows * params.stride)
+            .enumerate(),
+    );
+
+    thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut max_iterations: u32 = 0;
+
+                    loop {
+                        let band = bands.lock().unwrap().next();
+
+                        match band {
+                            Some((i, rows)) => {
+                                max_iterations = cmp::max(
+                                    max_iterations,
+                                    iterate_band(rows, i * band_rows, params),
+                                )
+                            }
+                            None => break max_iterations,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|w| w.join().unwrap())
+            .max()
+            .unwrap_or(0)
+    })
+}
+
+/// Makes every sample but the first one of each pixel (the one at the
+/// pixel's point) dormant, for adaptive supersampling
+fn put_samples_to_sleep(values: &mut ImageBuffer<Orbit>, samples: usize) {
+    for row in values.rows_mut() {
+        for orbits in row.chunks_mut(samples) {
+            for orbit in orbits[1..].iter_mut() {
+                orbit.dormant = true;
+            }
+        }
+    }
+}
+
+/// Wakes up the samples of the pixels whose color differs from one of their
+/// neighbours' by more than `threshold` (going by the color of the first
+/// sample of each pixel, which is at the pixel's point)
+///
+/// Returns whether any samples were woken up.
+fn wake_samples<P, F>(
+    values: &mut ImageBuffer<Orbit>,
+    samples: usize,
+    threshold: f64,
+    color: F,
+) -> bool
 where
-    f64: From<T>,
+    P: 'static + Unsigned + Bounded + UpperHex + Copy + Zero + Into<f64>,
+    F: Fn(&Orbit) -> Pixel<P>,
 {
-    let mut count = 0;
-    let (finished_iters, mut z) = current_coord;
-    let two = ComplexNumber::<f64>::new(2.0, 0.0);
+    let (w, h) = (values.width() / samples as u32, values.height());
+    let mut colors = ImageBuffer::new(w, h, Pixel::<P>::default())
+        .expect("the dimensions were checked when the Mandelbrot was made");
 
-    while two > z && count < limit {
-        z = z * z + c;
-        count += 1;
+    for (pixels, orbits) in colors.rows_mut().zip(values.rows()) {
+        for (pixel, orbits) in pixels.iter_mut().zip(orbits.chunks(samples)) {
+            *pixel = color(&orbits[0]);
+        }
+    }
+
+    let mut woke = false;
+
+    for y in 0..h {
+        for x in 0..w {
+            let first = x as usize * samples;
+            if !values.row(y)[first + 1].dormant {
+                continue;
+            }
+
+            let color = &colors[(x, y)];
+            let stands_out = [(0, -1), (-1, 0), (1, 0), (0, 1)].iter().any(|&(dx, dy)| {
+                let (nx, ny) = (i64::from(x) + dx, i64::from(y) + dy);
+
+                nx >= 0
+                    && ny >= 0
+                    && colors
+                        .get(nx as u32, ny as u32)
+                        .is_some_and(|neighbour| sampling::difference(color, neighbour) > threshold)
+            });
+
+            if stands_out {
+                for orbit in values.row_mut(y)[first + 1..first + samples].iter_mut() {
+                    orbit.dormant = false;
+                }
+                woke = true;
+            }
+        }
+    }
+
+    woke
+}
+
+/// How many bands of rows each thread gets (roughly) in `run_iterations`
+const BANDS_PER_THREAD: usize = 4;
+
+/// The settings `iterate_band` needs, bundled up so they can be shared with
+/// each thread
+struct IterationParams<'a> {
+    /// The coordinate of the top left pixel (relative to the reference point
+    /// when rendering with perturbation)
+    origin: ComplexNumber<f64>,
+    /// The distance between pixels along a row and along a column
+    steps: (ComplexNumber<f64>, ComplexNumber<f64>),
+    /// The number of `Orbit`s in a row
+    width: usize,
+    /// The number of `Orbit`s from the start of one row to the next
+    stride: usize,
+    /// The number of iterations to run
+    limit: u32,
+    /// The number of iterations every orbit should have been through once
+    /// this is done (orbits that haven't been started yet get this many)
+    total: u32,
+    /// The number of `Orbit`s (samples) each pixel has
+    samples: usize,
+    /// Where the samples go
+    supersampling: Supersampling,
+    /// Whether each pixel's first sample is at the pixel's point, ahead of
+    /// the samples `supersampling` places (for adaptive supersampling)
+    centered: bool,
+    /// The escape radius, squared
+    escape_radius_sqr: f64,
+    /// Whether to skip points in the main cardioid and the period-2 bulb
+    interior_check: bool,
+    /// Whether to look for orbits that have settled into a cycle
+    periodicity_check: bool,
+    /// How close (squared) an orbit has to come back to its checkpoint to
+    /// count as a cycle, for perturbation (see `PERIODICITY_PIXEL_FRACTION`)
+    periodicity_tolerance_sqr: f64,
+    /// The reference orbit, if rendering with perturbation
+    reference: Option<&'a [ComplexNumber<f64>]>,
+    /// The constant to use for every pixel, in Julia mode
+    julia: Option<ComplexNumber<f64>>,
+    /// The formula to iterate
+    formula: &'a dyn Formula,
+    /// The derivative to track (for distance estimation)
+    derivative: Derivative,
+    /// The glitch tolerance (for perturbation), squared
+    glitch_tolerance_sqr: f64,
+    /// A series approximation to start every orbit with (for perturbation,
+    /// only on the first pass)
+    series: Option<&'a SeriesApproximation>,
+}
+
+/// Which derivative of `z` (if any) to track alongside it, for distance
+/// estimation
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Derivative {
+    Off,
+    /// `dz/dc`, for the Mandelbrot Set
+    C,
+    /// `dz/dz0`, for Julia Sets
+    Z0,
+}
+
+impl Derivative {
+    /// Moves `dz` along one step, given the derivative of the step at the
+    /// current `z` (if the formula has one)
+    fn step(self, dz: ComplexNumber<f64>, step: Option<ComplexNumber<f64>>) -> ComplexNumber<f64> {
+        match (self, step) {
+            (Derivative::Off, _) => dz,
+            (_, None) => ComplexNumber::new(f64::NAN, f64::NAN),
+            (Derivative::C, Some(step)) => step * dz + 1.0,
+            (Derivative::Z0, Some(step)) => step * dz,
+        }
+    }
+}
+
+impl<'a> IterationParams<'a> {
+    /// Where sample `sample` of the pixel at column `x` of row `y` is,
+    /// relative to the pixel's point
+    fn sample_offset(&self, x: usize, y: usize, sample: usize) -> ComplexNumber<f64> {
+        let pixel = (x as u32, y as u32);
+        let (u, v) = match (self.centered, sample) {
+            (true, 0) => (0.0, 0.0),
+            (true, s) => self.supersampling.offset(pixel, s - 1),
+            (false, s) => self.supersampling.offset(pixel, s),
+        };
+
+        let (d_w, d_h) = self.steps;
+        d_w * u + d_h * v
     }
+}
+
+/// A helper function that runs the number of iterations given on a band of
+/// rows (a chunk of an `ImageBuffer`'s elements), starting at row `first_row`
+///
+/// Orbits that haven't been started yet (that haven't been through any
+/// iterations) are run up to the total number of iterations instead, which
+/// is how samples that adaptive supersampling wakes up catch up; orbits that
+/// escaped right away are the only other ones without any iterations, and
+/// running those again doesn't do anything. Dormant orbits are skipped.
+///
+/// Returns the largest iteration count in the band.
+fn iterate_band(band: &mut [Orbit], first_row: usize, params: &IterationParams) -> u32 {
+    let (d_w, d_h) = params.steps;
+    let mut max_iterations: u32 = 0;
+
+    for (r, row) in band.chunks_mut(params.stride.max(1)).enumerate() {
+        let r = first_row + r;
+
+        for (c, orbit) in row[..params.width].iter_mut().enumerate() {
+            if orbit.dormant {
+                continue;
+            }
+
+            let (x, sample) = (c / params.samples, c % params.samples);
+            let mut coordinate = params.origin + d_w * (x as f64) + d_h * (r as f64);
+            if params.samples > 1 {
+                coordinate += params.sample_offset(x, r, sample);
+            }
+
+            let limit = if orbit.iterations == 0 {
+                params.total
+            } else {
+                params.limit
+            };
+
+            if params.interior_check && orbit.period.is_none() {
+                if let Some(period) = cardioid_or_bulb_period(coordinate) {
+                    // Skipped points still get the values an orbit that
+                    // found its cycle would have:
+                    orbit.z = cycle_point(coordinate, period);
+                    orbit.period = Some(period);
+                    orbit.multiplier =
+                        cycle_multiplier(orbit.z, coordinate, period, &Multibrot::default());
+
+                    if params.derivative == Derivative::C {
+                        orbit.interior_distance = interior_distance(orbit.z, coordinate, period);
+                    }
+                }
+            }
+
+            // Points we already know are in the set don't need to be iterated:
+            if orbit.period.is_some() {
+                orbit.iterations += limit;
+            } else if let Some(reference) = params.reference {
+                *orbit = iterate_perturbed(*orbit, coordinate, reference, limit, params);
+            } else {
+                let c = match params.julia {
+                    Some(c) => {
+                        // The pixel's coordinate is where the orbit starts:
+                        if orbit.iterations == 0 {
+                            orbit.z = coordinate;
+                            orbit.dz = ComplexNumber::new(1.0, 0.0);
+                        }
 
-    (count + finished_iters, z)
+                        c
+                    }
+                    None => coordinate,
+                };
+
+                // The usual formula gets its own copy of the loop so that the
+                // step can be inlined:
+                *orbit = if params.formula.is_quadratic() {
+                    iterate_coordinate(
+                        *orbit,
+                        c,
+                        &Multibrot::default(),
+                        limit,
+                        params.escape_radius_sqr,
+                        params.periodicity_check,
+                        params.derivative,
+                    )
+                } else {
+                    iterate_coordinate(
+                        *orbit,
+                        c,
+                        params.formula,
+                        limit,
+                        params.escape_radius_sqr,
+                        params.periodicity_check,
+                        params.derivative,
+                    )
+                };
+            }
+
+            max_iterations = cmp::max(max_iterations, orbit.iterations);
+        }
+    }
+
+    max_iterations
+}
+
+/// Checks whether a point is inside the main cardioid (period 1) or the
+/// period-2 bulb of the Mandelbrot Set and returns the period if so
+///
+/// Both tests are strict so that points sitting right on the boundaries still
+/// get iterated normally.
+fn cardioid_or_bulb_period(c: ComplexNumber<f64>) -> Option<u32> {
+    let (x, y) = (c.real(), c.imag());
+    let y2 = y * y;
+
+    // Main cardioid:
+    let q = (x - 0.25) * (x - 0.25) + y2;
+    if q * (q + (x - 0.25)) < 0.25 * y2 {
+        return Some(1);
+    }
+
+    // Period-2 bulb (the circle of radius 1/4 around -1):
+    if (x + 1.0) * (x + 1.0) + y2 < 0.0625 {
+        return Some(2);
+    }
+
+    None
+}
+
+/// A point on the attracting cycle of a point in the main cardioid (period 1)
+/// or the period-2 bulb
+///
+/// These are the roots of `z² + c = z` and `z² + z + c + 1 = 0` (what's left
+/// of `(z² + c)² + c = z` once the fixed points are divided out).
+fn cycle_point(c: ComplexNumber<f64>, period: u32) -> ComplexNumber<f64> {
+    let one = ComplexNumber::new(1.0, 0.0);
+
+    match period {
+        1 => (one - (one - c * 4.0).sqrt()) / 2.0,
+        _ => ((-(c * 4.0) - 3.0).sqrt() - 1.0) / 2.0,
+    }
+}
+
+/// How close (squared) a point in an orbit has to get to the checkpoint for
+/// the orbit to be considered periodic
+const PERIODICITY_TOLERANCE_SQR: f64 = 1e-24;
+
+/// With perturbation, neighbouring pixels can be closer together than
+/// `PERIODICITY_TOLERANCE_SQR` allows for, so the tolerance shrinks to this
+/// fraction of a pixel (if that's smaller)
+const PERIODICITY_PIXEL_FRACTION: f64 = 1e-3;
+
+/// The periodicity tolerance (squared) for perturbation, for pixels
+/// `pixel_size` apart
+fn periodicity_tolerance_sqr(pixel_size: f64) -> f64 {
+    let tolerance = pixel_size * PERIODICITY_PIXEL_FRACTION;
+    PERIODICITY_TOLERANCE_SQR.min(tolerance * tolerance)
+}
+
+/// The longest we'll go between moving checkpoints when checking for periodic
+/// orbits
+const MAX_CHECKPOINT_INTERVAL: u32 = 1 << 20;
+
+/// Works out the multiplier of the cycle of the given period that `z` is on
+/// (the derivative of the orbit with respect to z over a trip around the
+/// cycle) and returns it if the cycle is attracting (if its magnitude is less
+/// than 1)
+///
+/// Cycles of formulas without a derivative are never considered attracting,
+/// which turns the periodicity check off for them.
+fn cycle_multiplier<F: Formula + ?Sized>(
+    z: ComplexNumber<f64>,
+    c: ComplexNumber<f64>,
+    period: u32,
+    formula: &F,
+) -> Option<ComplexNumber<f64>> {
+    let mut z = z;
+    let mut multiplier = ComplexNumber::new(1.0, 0.0);
+
+    for _ in 0..period {
+        multiplier = match formula.derivative(z) {
+            Some(derivative) => multiplier * derivative,
+            None => return None,
+        };
+        z = formula.iterate(z, c);
+    }
+
+    if multiplier.norm_sqr() < 1.0 {
+        Some(multiplier)
+    } else {
+        None
+    }
+}
+
+/// Estimates how far `c` is from the edge of the Mandelbrot Set, given a point
+/// `z` on the attracting cycle (of the given period) its orbit settled into
+///
+/// With `f` being `period` steps of `z² + c`, this is
+/// `(1 - |∂f/∂z|²) / |∂²f/∂z∂c + ∂²f/∂z² ∂f/∂c / (1 - ∂f/∂z)|`, with the
+/// derivatives taken at `z`.
+fn interior_distance(z: ComplexNumber<f64>, c: ComplexNumber<f64>, period: u32) -> Option<f64> {
+    let (zero, one) = (ComplexNumber::new(0.0, 0.0), ComplexNumber::new(1.0, 0.0));
+    let (mut z, mut dz, mut dc, mut dzdz, mut dcdz) = (z, one, zero, zero, zero);
+
+    for _ in 0..period {
+        // (Each of these needs the values from before the step)
+        dcdz = (dcdz * z + dc * dz) * 2.0;
+        dzdz = (dzdz * z + dz * dz) * 2.0;
+        dc = dc * z * 2.0 + 1.0;
+        dz = dz * z * 2.0;
+        z = z * z + c;
+    }
+
+    let estimate = (1.0 - dz.norm_sqr()) / (dcdz + dzdz * dc / (one - dz)).abs();
+
+    if estimate.is_finite() && estimate > 0.0 {
+        Some(estimate)
+    } else {
+        None
+    }
+}
+
+/// A helper function that runs the number of iterations given on a single
+/// coordinate
+///
+/// When a point has escaped is up to the formula (usually it's once its
+/// magnitude reaches the escape radius; we compare squared magnitudes so we
+/// don't have to take a square root every step).
+///
+/// If `periodicity_check` is set, orbits are checked for cycles with Brent's
+/// algorithm: each point in the orbit is compared against a checkpoint which
+/// is moved up to the current point every time the number of steps since the
+/// last move hits a power of two. When an orbit comes back to its checkpoint
+/// we make sure the cycle it found is attracting (orbits of points just
+/// outside the set can linger on repelling cycles for a while before
+/// escaping); if it is, the orbit will stay on it forever, so we record the
+/// period and the multiplier (and the interior distance estimate, when
+/// distance estimation is on) and skip the rest of the iterations.
+///
+/// The derivative asked for (if any) is updated before each step, since it
+/// depends on the `z` going into the step.
+fn iterate_coordinate<F: Formula + ?Sized>(
+    orbit: Orbit,
+    c: ComplexNumber<f64>,
+    formula: &F,
+    limit: u32,
+    escape_radius_sqr: f64,
+    periodicity_check: bool,
+    derivative: Derivative,
+) -> Orbit {
+    let mut orbit = orbit;
+    let mut count = 0;
+
+    while !formula.escaped(orbit.z, escape_radius_sqr) && count < limit {
+        if derivative != Derivative::Off {
+            orbit.dz = derivative.step(orbit.dz, formula.derivative(orbit.z));
+        }
+
+        orbit.z = formula.iterate(orbit.z, c);
+        count += 1;
+
+        if periodicity_check && orbit.check_periodicity(c, formula, PERIODICITY_TOLERANCE_SQR) {
+            if derivative == Derivative::C && formula.is_quadratic() {
+                orbit.interior_distance = orbit
+                    .period
+                    .and_then(|period| interior_distance(orbit.z, c, period));
+            }
+
+            count = limit;
+            break;
+        }
+    }
+
+    orbit.iterations += count;
+    orbit
+}
+
+/// Like `iterate_coordinate`, but for perturbation rendering: `dc` is the
+/// offset of the point from the reference point and only the difference
+/// between the point's orbit and the reference orbit is iterated (see the
+/// [`perturbation`](../perturbation/index.html) module)
+///
+/// If there's a series approximation, the orbit skips straight to the end of
+/// it.
+fn iterate_perturbed(
+    orbit: Orbit,
+    dc: ComplexNumber<f64>,
+    reference: &[ComplexNumber<f64>],
+    limit: u32,
+    params: &IterationParams,
+) -> Orbit {
+    let mut orbit = orbit;
+    let mut count = 0;
+
+    // This is only used to check whether cycles are attracting, so it's fine
+    // that it isn't exact:
+    let c = reference.get(1).map_or(dc, |rc| *rc + dc);
+
+    if let Some(series) = params.series {
+        let skipped = series.iterations();
+
+        orbit.delta = series.evaluate(dc);
+        if params.derivative != Derivative::Off {
+            orbit.dz = series.derivative(dc);
+        }
+        orbit.reference_index = skipped;
+        orbit.z = reference[skipped] + orbit.delta;
+        count = skipped as u32;
+    }
+
+    while orbit.z.norm_sqr() < params.escape_radius_sqr && count < limit {
+        // If the reference orbit escaped before this one, go back to its start:
+        if orbit.reference_index + 1 >= reference.len() {
+            orbit.rebase();
+        }
+
+        let r = orbit.reference_index;
+
+        // (`orbit.z` is the full value, so this is the same as it is without
+        // perturbation)
+        if params.derivative != Derivative::Off {
+            orbit.dz = orbit.dz * orbit.z * 2.0 + 1.0;
+        }
+
+        orbit.delta = reference[r] * orbit.delta * 2.0 + orbit.delta * orbit.delta + dc;
+        orbit.reference_index += 1;
+        orbit.z = reference[r + 1] + orbit.delta;
+        count += 1;
+
+        // Rebase when we spot a glitch (Pauldelbrot's criterion) and whenever
+        // the orbit ends up closer to 0 than to the reference orbit:
+        let z_sqr = orbit.z.norm_sqr();
+        if z_sqr < params.glitch_tolerance_sqr * reference[r + 1].norm_sqr()
+            || z_sqr < orbit.delta.norm_sqr()
+        {
+            orbit.rebase();
+        }
+
+        // (Perturbation is only ever used with the usual formula)
+        if params.periodicity_check
+            && orbit.check_periodicity(c, &Multibrot::default(), params.periodicity_tolerance_sqr)
+        {
+            if params.derivative != Derivative::Off {
+                orbit.interior_distance = orbit
+                    .period
+                    .and_then(|period| interior_distance(orbit.z, c, period));
+            }
+
+            count = limit;
+            break;
+        }
+    }
+
+    orbit.iterations += count;
+    orbit
+}
+
+#[cfg(test)]
+mod tests {
+
+    use big_float::BigFloat;
+    use color_scale::{
+        BandThresholds, ColorScale, ContinuousColorScale, DiscreteColorScale, DistanceColorScale,
+        GradientColorScale, HistogramColorScale, InteriorColoring,
+    };
+    use complex_number::ComplexNumber;
+    use error::Error;
+    use formula::{BurningShip, Celtic, Formula, Multibrot, RealMultibrot, Tricorn};
+    use gradient::{Color, Gradient, Wrap};
+    use histogram::Histogram;
+    use mandelbrot::num_traits::Zero;
+    use mandelbrot::{
+        cardioid_or_bulb_period, iterate_coordinate, periodicity_tolerance_sqr, Derivative,
+        FrameStats, Mandelbrot, MandelbrotConfig, Orbit, RenderInfo, Viewport,
+        PERIODICITY_TOLERANCE_SQR,
+    };
+    use perturbation::{Perturbation, Reference, ReferenceOrbit, SeriesApproximation};
+    use pixel::{Pixel, PixelMath};
+    use sampling::{self, Supersampling};
+    use std::sync::Arc;
+
+    fn config(dimensions: (u32, u32)) -> MandelbrotConfig<u8> {
+        let viewport = Viewport::new(ComplexNumber::new(-2.5, 1.25), 3.5, 2.5);
+
+        MandelbrotConfig::<u8>::new(
+            dimensions,
+            viewport,
+            Box::new(DiscreteColorScale::default()),
+        )
+    }
+
+    fn render(threads: usize, dimensions: (u32, u32), iters: u32) -> Mandelbrot<u8> {
+        let mut config = config(dimensions);
+        config.threads = threads;
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(iters);
+        mandelbrot
+    }
+
+    #[test]
+    fn threads_match_serial() {
+        let serial = render(1, (211, 97), 60);
+
+        for threads in [2, 3, 8, 500].iter() {
+            let parallel = render(*threads, (211, 97), 60);
+
+            assert_eq!(serial.get_pixels(), parallel.get_pixels());
+            assert_eq!(serial.values, parallel.values);
+        }
+    }
+
+    #[test]
+    fn escape_radius() {
+        // 0 -> 1 -> 2 -> 5 -> 26 -> 677 -> 458330 -> ~2.1e11
+        let c = ComplexNumber::new(1.0, 0.0);
+        let start = Orbit::default();
+
+        let orbit = iterate_coordinate(
+            start,
+            c,
+            &Multibrot::new(2).unwrap(),
+            100,
+            2.0 * 2.0,
+            false,
+            Derivative::Off,
+        );
+        assert_eq!(orbit.iterations, 2);
+        assert_eq!(orbit.z, ComplexNumber::new(2.0, 0.0));
+
+        let orbit = iterate_coordinate(
+            start,
+            c,
+            &Multibrot::new(2).unwrap(),
+            100,
+            1e10 * 1e10,
+            false,
+            Derivative::Off,
+        );
+        assert_eq!(orbit.iterations, 7);
+        assert_eq!(orbit.z, ComplexNumber::new(210066388901.0, 0.0));
+
+        // Points that don't escape shouldn't care:
+        let c = ComplexNumber::new(-1.0, 0.0);
+        assert_eq!(
+            iterate_coordinate(
+                start,
+                c,
+                &Multibrot::new(2).unwrap(),
+                100,
+                4.0,
+                false,
+                Derivative::Off
+            )
+            .iterations,
+            100
+        );
+        assert_eq!(
+            iterate_coordinate(
+                start,
+                c,
+                &Multibrot::new(2).unwrap(),
+                100,
+                1e20,
+                false,
+                Derivative::Off
+            )
+            .iterations,
+            100
+        );
+    }
+
+    #[test]
+    fn cardioid_and_bulb() {
+        let inside = [
+            (0.0, 0.0, 1),
+            (-0.5, 0.5, 1),
+            (0.2, 0.0, 1),
+            (-1.0, 0.0, 2),
+            (-1.2, 0.1, 2),
+        ];
+        let outside = [
+            (0.3, 0.0),
+            (-0.75, 0.1),
+            (-1.3, 0.0),
+            (0.0, 1.0),
+            (-2.0, 0.0),
+        ];
+
+        for (x, y, period) in inside.iter() {
+            assert_eq!(
+                cardioid_or_bulb_period(ComplexNumber::new(*x, *y)),
+                Some(*period)
+            );
+        }
+
+        for (x, y) in outside.iter() {
+            assert_eq!(cardioid_or_bulb_period(ComplexNumber::new(*x, *y)), None);
+        }
+    }
+
+    fn run_with_checks(interior_check: bool, periodicity_check: bool) -> Mandelbrot<u8> {
+        let mut config = config((320, 240));
+        config.color_fn = Box::new(ContinuousColorScale::new(140.0, 1.0, 1.0, 10.0).unwrap());
+        config.interior_check = interior_check;
+        config.periodicity_check = periodicity_check;
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+
+        // In a couple of steps to make sure skipped points keep up:
+        mandelbrot.run_iterations(60);
+        mandelbrot.run_iterations(140);
+        mandelbrot
+    }
+
+    #[test]
+    fn continuous_scales() {
+        // Any finite scale is fine (it's in degrees per iteration):
+        assert!(ContinuousColorScale::new(140.0, 1.0, 1.0, 400.0).is_ok());
+        assert!(ContinuousColorScale::new(140.0, 1.0, 1.0, -25.0).is_ok());
+
+        assert!(ContinuousColorScale::new(140.0, 1.0, 1.0, f64::NAN).is_err());
+        assert!(ContinuousColorScale::new(140.0, 1.0, 1.0, f64::INFINITY).is_err());
+        assert!(ContinuousColorScale::new(140.0, 1.0, 1.0, 1e305).is_err());
+        assert!(ContinuousColorScale::new(f64::NAN, 1.0, 1.0, 10.0).is_err());
+        assert!(ContinuousColorScale::new(140.0, 1.5, 1.0, 10.0).is_err());
+    }
+
+    #[test]
+    fn interior_checks_match_brute_force() {
+        let brute_force = run_with_checks(false, false);
+
+        for (interior, periodicity) in [(true, false), (false, true), (true, true)].iter() {
+            let fast = run_with_checks(*interior, *periodicity);
+
+            assert_eq!(fast.get_pixels(), brute_force.get_pixels());
+
+            for (a, b) in fast.values.iter().zip(brute_force.values.iter()) {
+                assert_eq!(a.iterations, b.iterations);
+            }
+        }
+    }
+
+    #[test]
+    fn periodicity() {
+        let period = |x: f64, y: f64| {
+            let c = ComplexNumber::new(x, y);
+            iterate_coordinate(
+                Orbit::default(),
+                c,
+                &Multibrot::new(2).unwrap(),
+                10_000,
+                4.0,
+                true,
+                Derivative::Off,
+            )
+            .period
+        };
+
+        assert_eq!(period(0.0, 0.0), Some(1));
+        assert_eq!(period(-0.2, 0.3), Some(1));
+        assert_eq!(period(-1.0, 0.0), Some(2));
+        assert_eq!(period(-1.755, 0.0), Some(3));
+        assert_eq!(period(-0.12, 0.75), Some(3));
+        assert_eq!(period(-1.3, 0.0), Some(4));
+
+        // Points that escape don't have a period:
+        assert_eq!(period(0.3, 0.0), None);
+        assert_eq!(period(-0.75, 0.1), None);
+
+        // Even if they spend a while stuck on a (repelling) cycle first; `i`
+        // lands on a 2-cycle after one iteration, so points right next to it
+        // stay near that cycle for a bit before escaping:
+        assert_eq!(period(0.0, 1.0 + 1e-15), None);
+
+        // Points that are found to be periodic still count as having gone
+        // through all their iterations:
+        let c = ComplexNumber::new(-1.0, 0.0);
+        let orbit = iterate_coordinate(
+            Orbit::default(),
+            c,
+            &Multibrot::new(2).unwrap(),
+            10_000,
+            4.0,
+            true,
+            Derivative::Off,
+        );
+        assert_eq!(orbit.iterations, 10_000);
+    }
+
+    /// Renders the usual view with and without perturbation (using the
+    /// reference point given) and returns the fraction of points whose
+    /// iteration counts match
+    fn perturbation_agreement(reference: ComplexNumber<f64>) -> f64 {
+        let render = |perturbation: Option<Perturbation>| {
+            let mut config = config((160, 120));
+            config.periodicity_check = false;
+            config.interior_check = false;
+
+            if let Some(perturbation) = perturbation {
+                let top_left = config.viewport.top_left - reference;
+                config.viewport.top_left = top_left;
+                config.perturbation = Some(perturbation);
+            }
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(50);
+            mandelbrot.run_iterations(50);
+            mandelbrot
+        };
+
+        let direct = render(None);
+        let perturbed = render(Some(Perturbation::new(ReferenceOrbit::new(reference))));
+
+        let matches = direct
+            .values
+            .iter()
+            .zip(perturbed.values.iter())
+            .filter(|(a, b)| a.iterations == b.iterations)
+            .count();
+
+        matches as f64 / (160.0 * 120.0)
+    }
+
+    #[test]
+    fn perturbation_matches_direct() {
+        // A reference point in the set:
+        assert!(perturbation_agreement(ComplexNumber::new(-0.5, 0.25)) > 0.995);
+        assert!(perturbation_agreement(ComplexNumber::new(-1.0, 0.0)) > 0.995);
+    }
+
+    #[test]
+    fn perturbation_rebases() {
+        // A reference point that escapes almost immediately means (nearly)
+        // every point has to be rebased at some point:
+        assert!(perturbation_agreement(ComplexNumber::new(1.0, 0.0)) > 0.995);
+        assert!(perturbation_agreement(ComplexNumber::new(-2.0, 1.0)) > 0.995);
+    }
+
+    #[test]
+    fn perturbation_deep_zoom() {
+        // At this zoom, pixels are closer together than `f64`s can resolve
+        // around this point, so rendering directly gives us blocks of pixels
+        // with the same coordinate:
+        let center = ComplexNumber::new(-0.743643887037151, 0.131825904205330);
+        let width = 1e-15;
+        let offset = ComplexNumber::new(-width / 2.0, 0.0);
+
+        let render = |perturbation: bool| {
+            let mut config = config((64, 1));
+            let top_left = if perturbation {
+                offset
+            } else {
+                center + offset
+            };
+            config.viewport = Viewport::new(top_left, width, width / 64.0);
+
+            if perturbation {
+                config.perturbation = Some(Perturbation::new(ReferenceOrbit::new(center)));
+            }
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(5000);
+
+            let mut iterations: Vec<u32> = mandelbrot
+                .values
+                .row(0)
+                .iter()
+                .map(|orbit| orbit.iterations)
+                .collect();
+            iterations.dedup();
+            iterations.len()
+        };
+
+        assert!(render(false) <= 16);
+        assert!(render(true) >= 32);
+    }
+
+    #[test]
+    fn perturbation_periodicity_at_deep_zoom() {
+        // Pixels here are far closer together than the usual periodicity
+        // tolerance, which mustn't make escaping points look periodic:
+        let center = ComplexNumber::new(-0.743643887037151, 0.131825904205330);
+        let width = 1e-15;
+
+        let render = |periodicity_check: bool| {
+            let mut config = config((64, 4));
+            config.viewport =
+                Viewport::new(ComplexNumber::new(-width / 2.0, 0.0), width, width / 16.0);
+            config.perturbation = Some(Perturbation::new(ReferenceOrbit::new(center)));
+            config.periodicity_check = periodicity_check;
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(5000);
+            mandelbrot
+                .values
+                .iter()
+                .map(|orbit| orbit.iterations)
+                .collect::<Vec<u32>>()
+        };
+
+        assert_eq!(render(true), render(false));
+
+        // The tolerance stays well under the distance between pixels:
+        assert_eq!(periodicity_tolerance_sqr(1e-3), PERIODICITY_TOLERANCE_SQR);
+        for &pixel_size in [1e-12, 1e-17, 1e-30].iter() {
+            assert!(periodicity_tolerance_sqr(pixel_size) < pixel_size * pixel_size * 1e-5);
+        }
+    }
+
+    #[test]
+    fn series_approximation_matches_full_iteration() {
+        // Deep in the seahorse valley, where every pixel spends its first few
+        // hundred iterations following the reference orbit closely:
+        let center = ComplexNumber::new(-0.743643887037151, 0.131825904205330);
+        let (width, pixels, iterations) = (1e-12, 64, 1000);
+
+        let render = |series_terms: usize| {
+            let mut perturbation = Perturbation::new(ReferenceOrbit::new(center));
+            perturbation.series_terms = series_terms;
+
+            let mut config = config((pixels, pixels));
+            config.viewport =
+                Viewport::new(ComplexNumber::new(-width / 2.0, width / 2.0), width, width);
+            config.perturbation = Some(perturbation);
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(iterations);
+            mandelbrot.run_iterations(iterations);
+            mandelbrot
+        };
+
+        // Check that the series actually gets us somewhere here:
+        let mut reference = ReferenceOrbit::new(center);
+        reference.extend(iterations as usize + 1, 4.0);
+        let series = SeriesApproximation::new(
+            reference.orbit(),
+            8,
+            width / 2f64.sqrt(),
+            width / pixels as f64,
+            1e-3,
+            2.0,
+            iterations as usize,
+        );
+        assert!(series.iterations() > 500);
+
+        let (full, skipped) = (render(0), render(8));
+        let matches = full
+            .values
+            .iter()
+            .zip(skipped.values.iter())
+            .filter(|(a, b)| a.iterations == b.iterations)
+            .count();
+
+        assert!(matches as f64 / (pixels * pixels) as f64 > 0.99);
+    }
+
+    #[test]
+    fn perturbation_big_float_reference() {
+        // Way past what `f64`s can do, next to `i` (a Misiurewicz point, so
+        // the escape times vary a lot); the reference point has more digits
+        // than an `f64` can hold:
+        let (re, im) = (
+            "0.0000000000000000000000123",
+            "1.00000000000000000000000456",
+        );
+        let (width, pixels, iterations) = (1e-22, 16, 500);
+
+        let mut config = config((pixels, 1));
+        config.viewport = Viewport::new(
+            ComplexNumber::new(-width / 2.0, 0.0),
+            width,
+            width / pixels as f64,
+        );
+        config.perturbation = Some(Perturbation::at(re, im, width, pixels).unwrap());
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(iterations);
+
+        // Iterate each pixel directly, at high precision:
+        type B = BigFloat<4>;
+        let center = ComplexNumber::<B>::new(re.parse().unwrap(), im.parse().unwrap());
+        let four = B::from_f64(4.0);
+
+        let brute_force = (0..pixels).map(|col| {
+            let offset =
+                B::from_f64(-width / 2.0) + B::from_f64(width / pixels as f64 * col as f64);
+            let c = center + ComplexNumber::new(offset, B::zero());
+
+            let mut z = ComplexNumber::new(B::zero(), B::zero());
+            let mut count = 0;
+            while z.norm_sqr() < four && count < iterations {
+                z = z * z + c;
+                count += 1;
+            }
+
+            count
+        });
+
+        let perturbed: Vec<u32> = mandelbrot
+            .values
+            .row(0)
+            .iter()
+            .map(|o| o.iterations)
+            .collect();
+        let brute_force: Vec<u32> = brute_force.collect();
+
+        let matches = perturbed
+            .iter()
+            .zip(brute_force.iter())
+            .filter(|(a, b)| a == b)
+            .count();
+        assert!(matches >= pixels as usize - 1);
+    }
+
+    #[test]
+    fn invalid_configs() {
+        let new = |config: MandelbrotConfig<u8>| Mandelbrot::new(config).err();
+
+        assert_eq!(
+            new(config((0, 10))),
+            Some(Error::ZeroDimensions {
+                width: 0,
+                height: 10
+            })
+        );
+        assert_eq!(
+            new(config((u32::MAX, u32::MAX))),
+            Some(Error::DimensionsTooLarge {
+                width: u32::MAX,
+                height: u32::MAX
+            })
+        );
+
+        let viewport = |top_left: ComplexNumber<f64>, width: f64, height: f64| {
+            let mut config = config((10, 10));
+            config.viewport = Viewport::new(top_left, width, height);
+            config
+        };
+
+        let origin = ComplexNumber::new(0.0, 0.0);
+        let invalid = |config| matches!(new(config), Some(Error::InvalidViewport(_)));
+
+        assert!(invalid(viewport(origin, 0.0, 1.0)));
+        assert!(invalid(viewport(origin, 1.0, -1.0)));
+        assert!(invalid(viewport(origin, f64::NAN, 1.0)));
+        assert!(invalid(viewport(origin, 1.0, f64::INFINITY)));
+        assert!(invalid(viewport(
+            ComplexNumber::new(f64::NAN, 0.0),
+            1.0,
+            1.0
+        )));
+        assert!(new(viewport(origin, 1.0, 1.0)).is_none());
+    }
+
+    fn close(a: ComplexNumber<f64>, b: ComplexNumber<f64>) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn viewport_constructors() {
+        let viewport = Viewport::from_center(ComplexNumber::new(-1.0, 0.5), 4.0, 2.0);
+        assert_eq!(
+            viewport,
+            Viewport::new(ComplexNumber::new(-3.0, 1.5), 4.0, 2.0)
+        );
+        assert_eq!(viewport.center(), ComplexNumber::new(-1.0, 0.5));
+
+        let corners =
+            Viewport::from_corners(ComplexNumber::new(1.0, -0.5), ComplexNumber::new(-3.0, 1.5));
+        assert_eq!(corners, viewport);
+
+        let zoomed = Viewport::from_center_zoom(ComplexNumber::new(-1.0, 0.5), 4.0, (200, 100));
+        assert_eq!(
+            zoomed,
+            Viewport::from_center(ComplexNumber::new(-1.0, 0.5), 1.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn viewport_fit_to() {
+        let square = Viewport::from_center(ComplexNumber::new(1.0, 1.0), 2.0, 2.0);
+
+        assert_eq!(
+            square.fit_to((200, 100)),
+            Viewport::from_center(ComplexNumber::new(1.0, 1.0), 4.0, 2.0)
+        );
+        assert_eq!(
+            square.fit_to((100, 400)),
+            Viewport::from_center(ComplexNumber::new(1.0, 1.0), 2.0, 8.0)
+        );
+        assert_eq!(square.fit_to((7, 7)), square);
+    }
+
+    #[test]
+    fn viewport_transforms() {
+        let dimensions = (100, 50);
+        let viewport = Viewport::new(ComplexNumber::new(-2.0, 1.0), 4.0, 2.0);
+
+        // The pixel we zoom in on shouldn't move:
+        let pixel = (20, 10);
+        let zoomed = viewport.zoom_at(pixel, 8.0, dimensions);
+        assert!(close(
+            zoomed.point_at(pixel, dimensions),
+            viewport.point_at(pixel, dimensions)
+        ));
+        assert_eq!((zoomed.width, zoomed.height), (0.5, 0.25));
+
+        // Zooming back out again should get us back to where we started:
+        let unzoomed = zoomed.zoom_at(pixel, 1.0 / 8.0, dimensions);
+        assert!(close(unzoomed.top_left, viewport.top_left));
+
+        let panned = viewport.pan(1.0, -0.5);
+        assert_eq!(panned.center(), ComplexNumber::new(1.0, -0.5));
+
+        // Rotating by a quarter turn turns "right" into "up":
+        let rotated = viewport.rotate(std::f64::consts::FRAC_PI_2);
+        assert_eq!(rotated.center(), viewport.center());
+        assert!(close(
+            rotated.pan(1.0, 0.0).center(),
+            ComplexNumber::new(0.0, 1.0)
+        ));
+
+        let (origin, d_w, d_h) = rotated.grid(dimensions);
+        assert!(close(origin, ComplexNumber::new(-1.0, -2.0)));
+        assert!(close(d_w, ComplexNumber::new(0.0, 0.04)));
+        assert!(close(d_h, ComplexNumber::new(0.04, 0.0)));
+
+        assert!(rotated.rotate(f64::NAN).validate().is_err());
+    }
+
+    #[test]
+    fn rotated_render() {
+        // Rotating a square view by a quarter turn should rotate the image:
+        let n = 60;
+        let viewport = Viewport::from_center(ComplexNumber::new(-0.5, 0.1), 2.5, 2.5);
+
+        let render = |viewport: Viewport<f64>| {
+            let mut config = config((n, n));
+            config.viewport = viewport;
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(50);
+            mandelbrot
+        };
+
+        let (plain, rotated) = (
+            render(viewport),
+            render(viewport.rotate(std::f64::consts::FRAC_PI_2)),
+        );
+
+        let mut matches = 0;
+        for y in 0..n {
+            for x in 1..n {
+                if rotated.values[(x, y)].iterations == plain.values[(y, n - x)].iterations {
+                    matches += 1;
+                }
+            }
+        }
+
+        assert!(f64::from(matches) / f64::from(n * (n - 1)) > 0.98);
+    }
+
+    #[test]
+    fn viewport_text() {
+        let viewport = Viewport::new(ComplexNumber::new(-2.5, 1.25), 3.5, 2.5);
+        assert_eq!(
+            viewport.to_string(),
+            "top_left=-2.5+1.25i width=3.5 height=2.5"
+        );
+        assert_eq!(
+            format!("{:.2}", viewport.rotate(0.125)),
+            "top_left=-2.50+1.25i width=3.50 height=2.50 rotation=0.12"
+        );
+
+        let parse = |s: &str| s.parse::<Viewport<f64>>();
+
+        assert_eq!(
+            parse("top_left=-2.5+1.25i width=3.5 height=2.5"),
+            Ok(viewport)
+        );
+        assert_eq!(
+            parse("  height=2.5 center=(-0.75, 0) width=3.5 rotation=0.5 "),
+            Ok(viewport.rotate(0.5))
+        );
+        assert_eq!(
+            parse("top_left=-2.5 + 1.25i width=3.5 height=2.5"),
+            Ok(viewport)
+        );
+
+        for bad in [
+            "",
+            "top_left=0 width=1",
+            "top_left=0 center=0 width=1 height=1",
+            "top_left=0 width=1 height=1 zoom=2",
+            "top_left=x width=1 height=1",
+            "top_left=0 width=1 height=one",
+            "0 width=1 height=1",
+        ]
+        .iter()
+        {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+
+        // Round trips are exact:
+        let viewports = [
+            Viewport::from_center_zoom(
+                ComplexNumber::new(-0.743643887037158, 0.131825904205311),
+                3.7e11,
+                (1920, 1080),
+            ),
+            Viewport::from_corners(
+                ComplexNumber::new(0.1, -0.0),
+                ComplexNumber::new(1.0 / 3.0, 0.7),
+            )
+            .rotate(-2.0 / 3.0),
+        ];
+
+        for viewport in viewports.iter() {
+            assert_eq!(parse(&viewport.to_string()), Ok(*viewport));
+        }
+    }
+
+    #[test]
+    fn julia() {
+        let c = ComplexNumber::new(-0.8, 0.156);
+        let dimensions = (48, 32);
+
+        let mut config = config(dimensions);
+        config.viewport = Viewport::from_center(ComplexNumber::new(0.0, 0.0), 3.0, 2.0);
+        config.julia = Some(c);
+        config.periodicity_check = false;
+
+        let viewport = config.viewport;
+        let mut julia = Mandelbrot::new(config).unwrap();
+        julia.run_iterations(30);
+        julia.run_iterations(30);
+
+        for y in 0..dimensions.1 {
+            for x in 0..dimensions.0 {
+                let mut z = viewport.point_at((x, y), dimensions);
+                let mut count = 0;
+                while z.norm_sqr() < 4.0 && count < 60 {
+                    z = z * z + c;
+                    count += 1;
+                }
+
+                assert_eq!(julia.values[(x, y)].iterations, count);
+            }
+        }
+    }
+
+    #[test]
+    fn julia_config() {
+        let dimensions = (40, 30);
+        let mandelbrot = Mandelbrot::new(config(dimensions)).unwrap();
+
+        let pixel = (12, 17);
+        let config = mandelbrot
+            .julia_config(pixel, Box::new(DiscreteColorScale::default()))
+            .unwrap();
+
+        let expected = mandelbrot.config.viewport.point_at(pixel, dimensions);
+        assert!(close(config.julia.unwrap(), expected));
+        assert_eq!(config.dimensions, dimensions);
+        assert_eq!(config.viewport.center(), ComplexNumber::new(0.0, 0.0));
+        assert_eq!(config.viewport.height, 4.0);
+
+        // The Julia Set for c = 0 is the unit disk:
+        let mut config = config;
+        config.julia = Some(ComplexNumber::new(0.0, 0.0));
+        let mut julia = Mandelbrot::new(config).unwrap();
+        julia.run_iterations(100);
+
+        let (origin, center) = (julia.values[(0, 0)], julia.values[(20, 15)]);
+        assert!(origin.iterations < 100);
+        assert_eq!(center.iterations, 100);
+        assert_eq!(center.period, Some(1));
+
+        // There's no way to pick a point on a perturbation render:
+        let mut config = self::config(dimensions);
+        config.perturbation = Some(Perturbation::new(ReferenceOrbit::new(expected)));
+        let perturbed = Mandelbrot::new(config).unwrap();
+        assert!(perturbed
+            .julia_config(pixel, Box::new(DiscreteColorScale::default()))
+            .is_err());
+
+        // And the two modes don't go together:
+        let mut config = self::config(dimensions);
+        config.julia = Some(expected);
+        config.perturbation = Some(Perturbation::new(ReferenceOrbit::new(expected)));
+        assert!(Mandelbrot::new(config).is_err());
+    }
+
+    #[test]
+    fn more_threads_than_rows() {
+        let serial = render(1, (40, 3), 20);
+        let parallel = render(16, (40, 3), 20);
+
+        assert_eq!(serial.get_pixels(), parallel.get_pixels());
+    }
+
+    #[test]
+    fn formulas() {
+        let dimensions = (30, 20);
+        let render = |formula: Arc<dyn Formula>, checks: bool| {
+            let mut config = config(dimensions);
+            config.formula = formula;
+            config.interior_check = checks;
+            config.periodicity_check = checks;
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(100);
+            mandelbrot
+        };
+        let iterations = |mandelbrot: &Mandelbrot<u8>, y: u32| -> Vec<u32> {
+            mandelbrot
+                .get_orbits()
+                .row(y)
+                .iter()
+                .map(|orbit| orbit.iterations)
+                .collect()
+        };
+        let all_iterations = |mandelbrot: &Mandelbrot<u8>| -> Vec<Vec<u32>> {
+            (0..dimensions.1)
+                .map(|y| iterations(mandelbrot, y))
+                .collect()
+        };
+
+        // On the real axis (row 10), all the quadratic variants are the same
+        // as the usual formula:
+        let usual = render(Arc::new(Multibrot::new(2).unwrap()), false);
+        let variants: Vec<Mandelbrot<u8>> = vec![
+            render(Arc::new(BurningShip), false),
+            render(Arc::new(Tricorn), false),
+            render(Arc::new(Celtic), false),
+        ];
+
+        for variant in variants.iter() {
+            assert_eq!(iterations(variant, 10), iterations(&usual, 10));
+            assert!(variant.get_orbits() != usual.get_orbits());
+        }
+
+        // The Tricorn is symmetric about the real axis:
+        for y in 1..10 {
+            assert_eq!(
+                iterations(&variants[1], y),
+                iterations(&variants[1], 20 - y)
+            );
+        }
+
+        // The cardioid/bulb check doesn't apply to other formulas, and without
+        // a derivative there's no periodicity check either:
+        let cubic = render(Arc::new(Multibrot::new(3).unwrap()), true);
+        assert_eq!(
+            all_iterations(&cubic),
+            all_iterations(&render(Arc::new(Multibrot::new(3).unwrap()), false))
+        );
+        let ship = render(Arc::new(BurningShip), true);
+        assert_eq!(all_iterations(&ship), all_iterations(&variants[0]));
+        assert!(ship.get_orbits().iter().all(|orbit| orbit.period.is_none()));
+
+        // Julia configs keep the formula:
+        let julia = cubic
+            .julia_config((5, 5), Box::new(DiscreteColorScale::default()))
+            .unwrap();
+        assert!(Arc::ptr_eq(&julia.formula, &cubic.config.formula));
+
+        // And perturbation only works with the usual formula:
+        let mut config = config(dimensions);
+        config.formula = Arc::new(Celtic);
+        config.perturbation = Some(Perturbation::new(ReferenceOrbit::new(ComplexNumber::new(
+            -0.75, 0.0,
+        ))));
+        assert!(matches!(
+            Mandelbrot::new(config).err(),
+            Some(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn smoothing_uses_the_degree() {
+        let c = ComplexNumber::new(0.6, 0.6);
+        let smooth = |formula: &dyn Formula, escape_radius: f64, degree: f64| {
+            let orbit = iterate_coordinate(
+                Orbit::default(),
+                c,
+                formula,
+                1000,
+                escape_radius * escape_radius,
+                false,
+                Derivative::Off,
+            );
+            assert!(orbit.iterations < 1000);
+
+            f64::from(orbit.iterations) + 1.0 - orbit.z.abs().log(10.0).log(degree)
+        };
+
+        // With the right degree, the smoothed count doesn't depend on the
+        // escape radius:
+        let formulas: Vec<Box<dyn Formula>> = vec![
+            Box::new(Multibrot::new(2).unwrap()),
+            Box::new(Multibrot::new(3).unwrap()),
+            Box::new(RealMultibrot::new(2.5).unwrap()),
+            Box::new(BurningShip),
+            Box::new(Celtic),
+        ];
+
+        for formula in formulas.iter() {
+            let degree = formula.degree();
+            assert!(
+                (smooth(&**formula, 1e10, degree) - smooth(&**formula, 1e40, degree)).abs() < 1e-3
+            );
+        }
+
+        // With the wrong one, it does:
+        let cubic = Multibrot::new(3).unwrap();
+        assert!((smooth(&cubic, 1e10, 2.0) - smooth(&cubic, 1e40, 2.0)).abs() > 0.1);
+    }
+
+    /// The distance estimate for a single point, if it escaped
+    fn estimate(c: ComplexNumber<f64>, julia: bool, distance_estimation: bool) -> Option<f64> {
+        let mut config = config((1, 1));
+        config.viewport = Viewport::from_center(c, 1e-9, 1e-9);
+        config.escape_radius = 1e10;
+        config.distance_estimation = distance_estimation;
+
+        if julia {
+            config.julia = Some(ComplexNumber::new(0.0, 0.0));
+        }
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(1000);
+
+        let orbit = mandelbrot.values[(0, 0)];
+        if orbit.iterations < 1000 {
+            orbit.distance_estimate(2.0)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn distance_estimates() {
+        // Along the real axis, the set goes from -2 to 1/4; the actual
+        // distance should be at least a quarter of the estimate (and the
+        // estimate shouldn't be too far off otherwise, even by the cusp):
+        for &(x, distance) in [(0.26, 0.01), (0.3, 0.05), (-2.05, 0.05), (-2.2, 0.2)].iter() {
+            let estimate = estimate(ComplexNumber::new(x, 0.0), false, true).unwrap();
+            assert!(
+                estimate / 4.0 <= distance && distance <= 10.0 * estimate,
+                "{}",
+                x
+            );
+        }
+
+        // The Julia Set for c = 0 is the unit circle:
+        let julia = estimate(ComplexNumber::new(0.0, 1.5), true, true).unwrap();
+        assert!(julia / 4.0 <= 0.5 && 0.5 <= julia);
+
+        // So is the one for `z³`, which gets an estimate scaled for its
+        // degree:
+        let mut config = config((1, 1));
+        config.viewport = Viewport::from_center(ComplexNumber::new(0.0, 1.5), 1e-9, 1e-9);
+        config.escape_radius = 1e10;
+        config.distance_estimation = true;
+        config.julia = Some(ComplexNumber::new(0.0, 0.0));
+        config.formula = Arc::new(Multibrot::new(3).unwrap());
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(1000);
+        let cubic = mandelbrot.values[(0, 0)].distance_estimate(3.0).unwrap();
+        assert!(cubic / 4.0 <= 0.5 && 0.5 <= cubic);
+        assert!((cubic - 1.5 * julia).abs() < 1e-6);
+
+        // Nothing gets an estimate when distance estimation is off:
+        assert_eq!(estimate(ComplexNumber::new(1.0, 0.0), false, false), None);
+    }
+
+    #[test]
+    fn distance_estimates_with_perturbation() {
+        let center = ComplexNumber::new(-0.743643887037158, 0.131825904205311);
+        let render = |perturbation: bool| {
+            let mut config = config((24, 16));
+            config.escape_radius = 1e5;
+            config.distance_estimation = true;
+            config.interior_check = false;
+            config.periodicity_check = false;
+
+            if perturbation {
+                config.viewport = Viewport::from_center(ComplexNumber::new(0.0, 0.0), 3e-6, 2e-6);
+                config.perturbation = Some(Perturbation::new(ReferenceOrbit::new(center)));
+            } else {
+                config.viewport = Viewport::from_center(center, 3e-6, 2e-6);
+            }
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(2000);
+            mandelbrot
+        };
+
+        let (direct, perturbed) = (render(false), render(true));
+        let (mut compared, mut matches) = (0, 0);
+
+        for (a, b) in direct.values.iter().zip(perturbed.values.iter()) {
+            if a.iterations < 2000 && a.iterations == b.iterations {
+                let (a, b) = (
+                    a.distance_estimate(2.0).unwrap(),
+                    b.distance_estimate(2.0).unwrap(),
+                );
+                compared += 1;
+
+                if (a - b).abs() < 1e-3 * a {
+                    matches += 1;
+                }
+            }
+        }
+
+        assert!(compared > 100);
+        assert!(f64::from(matches) / f64::from(compared) > 0.95);
+    }
+
+    #[test]
+    fn distance_color_scale() {
+        let dimensions = (120, 80);
+        let mut config = config(dimensions);
+        config.escape_radius = 1e10;
+        config.distance_estimation = true;
+        config.color_fn = Box::new(DistanceColorScale::new(200.0, 1.0, 8.0).unwrap());
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(200);
+
+        let pixels = mandelbrot.get_pixels();
+        let white = Pixel::new(255u8, 255, 255);
+
+        // The corner is far from the set, the middle of the cardioid is in it:
+        assert_eq!(pixels[(0, 0)], white);
+        assert_eq!(pixels[(70, 40)], Pixel::new(0, 0, 0));
+
+        // There's a dark line around the set, and a glow around that:
+        let dark = pixels
+            .iter()
+            .filter(|p| p.get_slice()[..3].iter().all(|v| *v < 128))
+            .count();
+        let tinted = pixels
+            .iter()
+            .filter(|p| **p != white && p.get_slice()[2] > 200)
+            .count();
+        let inside = mandelbrot
+            .get_orbits()
+            .iter()
+            .filter(|orbit| orbit.iterations == 200)
+            .count();
+
+        assert!(dark > inside + 100);
+        assert!(tinted > 100);
+
+        assert!(DistanceColorScale::new(200.0, -1.0, 8.0).is_err());
+        assert!(DistanceColorScale::new(f64::NAN, 1.0, 8.0).is_err());
+    }
+
+    /// The orbit of a single point after 1000 iterations, with distance
+    /// estimation on
+    fn interior(c: ComplexNumber<f64>, interior_check: bool) -> Orbit {
+        let mut config = config((1, 1));
+        config.viewport = Viewport::from_center(c, 1e-9, 1e-9);
+        config.distance_estimation = true;
+        config.interior_check = interior_check;
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(1000);
+        mandelbrot.values[(0, 0)]
+    }
+
+    #[test]
+    fn interior_values() {
+        let close = |a: ComplexNumber<f64>, b: ComplexNumber<f64>| (a - b).abs() < 1e-9;
+
+        // The center of the cardioid is superattracting (`interior` is off by
+        // half a pixel, which is close enough):
+        let center = interior(ComplexNumber::new(0.0, 0.0), true);
+        assert_eq!(center.period, Some(1));
+        assert!(close(
+            center.multiplier.unwrap(),
+            ComplexNumber::new(0.0, 0.0)
+        ));
+        assert!((center.interior_distance.unwrap() - 0.5).abs() < 1e-6);
+
+        // Half way to the cusp at -3/4, the fixed point is (1 - √3) / 2:
+        let half = interior(ComplexNumber::new(-0.5, 0.0), false);
+        let root = 3f64.sqrt();
+        assert!(close(half.z, ComplexNumber::new((1.0 - root) / 2.0, 0.0)));
+        assert!(close(
+            half.multiplier.unwrap(),
+            ComplexNumber::new(1.0 - root, 0.0)
+        ));
+
+        // Points the interior check skips get the same values they would
+        // have gotten from the periodicity check:
+        for &(x, y) in [(-0.5, 0.0), (0.1, 0.3), (-1.1, 0.1), (-0.9, -0.05)].iter() {
+            let c = ComplexNumber::new(x, y);
+            let (skipped, found) = (interior(c, true), interior(c, false));
+
+            assert_eq!(skipped.iterations, found.iterations);
+            assert_eq!(skipped.period, found.period);
+            assert!(close(
+                skipped.multiplier.unwrap(),
+                found.multiplier.unwrap()
+            ));
+
+            let (a, b) = (
+                skipped.interior_distance.unwrap(),
+                found.interior_distance.unwrap(),
+            );
+            assert!((a - b).abs() < 1e-6 * a);
+        }
+
+        // The period-2 bulb is a disk with a radius of 1/4:
+        let bulb = interior(ComplexNumber::new(-1.0, 0.0), false);
+        let estimate = bulb.interior_distance.unwrap();
+        assert_eq!(bulb.period, Some(2));
+        assert!(estimate / 4.0 <= 0.25 && 0.25 <= estimate);
+
+        // Points that escape don't get any of this:
+        let outside = interior(ComplexNumber::new(0.3, 0.0), true);
+        assert_eq!(
+            (outside.multiplier, outside.interior_distance),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn interior_coloring() {
+        let info = RenderInfo {
+            max_iterations: 1000,
+            escape_radius: 2.0,
+            degree: 2.0,
+            pixel_size: 1e-3,
+            histogram: None,
+            stats: FrameStats::default(),
+        };
+        let exterior = |_: &Orbit, _: &RenderInfo| Pixel::new(1u8, 2, 3);
+        let color = |coloring: InteriorColoring, c: ComplexNumber<f64>| {
+            coloring
+                .with_exterior(0.0, exterior)
+                .unwrap()
+                .pixel_color(&interior(c, true), &info)
+        };
+        let (cardioid, bulb, outside) = (
+            ComplexNumber::new(0.0, 0.0),
+            ComplexNumber::new(-1.0, 0.0),
+            ComplexNumber::new(1.0, 0.0),
+        );
+
+        // Each period gets its own color:
+        assert_eq!(
+            color(InteriorColoring::Period, cardioid),
+            Pixel::from_hsb(0.0, 0.8, 1.0).unwrap()
+        );
+        assert_eq!(
+            color(InteriorColoring::Period, bulb),
+            Pixel::from_hsb(137.5, 0.8, 1.0).unwrap()
+        );
+
+        // At 1/5, the multiplier is 1 - √(1/5), with a phase of 0:
+        assert_eq!(
+            color(
+                InteriorColoring::MultiplierPhase,
+                ComplexNumber::new(0.2, 0.0)
+            ),
+            Pixel::from_hsb(0.0, 0.8, 1.0 - (1.0 - 0.2f64.sqrt()) / 2.0).unwrap()
+        );
+        assert_eq!(color(InteriorColoring::Black, bulb), Pixel::new(0, 0, 0));
+        assert_ne!(color(InteriorColoring::Distance, bulb), Pixel::new(0, 0, 0));
+
+        // Everything else is left to the exterior coloring:
+        for coloring in [InteriorColoring::Magnitude, InteriorColoring::Distance].iter() {
+            assert_eq!(color(*coloring, outside), Pixel::new(1, 2, 3));
+        }
+
+        assert!(InteriorColoring::Period
+            .with_exterior(f64::NAN, exterior)
+            .is_err());
+    }
+
+    #[test]
+    fn interior_coloring_non_finite() {
+        let info = RenderInfo {
+            max_iterations: 10,
+            escape_radius: 2.0,
+            degree: 2.0,
+            pixel_size: 1e-3,
+            histogram: None,
+            stats: FrameStats::default(),
+        };
+        let nan = ComplexNumber::new(f64::NAN, f64::NAN);
+        let orbit = Orbit {
+            iterations: info.max_iterations,
+            z: nan,
+            interior_distance: Some(f64::NAN),
+            period: Some(1),
+            multiplier: Some(nan),
+            ..Default::default()
+        };
+
+        // Values that blew up give black instead of a panic:
+        for coloring in [
+            InteriorColoring::Distance,
+            InteriorColoring::Magnitude,
+            InteriorColoring::MultiplierPhase,
+        ]
+        .iter()
+        {
+            let exterior = |_: &Orbit, _: &RenderInfo| Pixel::new(1u8, 2, 3);
+            let scale = coloring.with_exterior(0.0, exterior).unwrap();
+            assert_eq!(scale.pixel_color(&orbit, &info), Pixel::new(0u8, 0, 0));
+        }
+    }
+
+    /// Black and white: white for points that escaped
+    fn outside_white(orbit: &Orbit, info: &RenderInfo) -> Pixel<u8> {
+        if orbit.iterations == info.max_iterations {
+            Pixel::new(0, 0, 0)
+        } else {
+            Pixel::new(255, 255, 255)
+        }
+    }
+
+    fn supersampled(
+        supersampling: Supersampling,
+        adaptive_threshold: Option<f64>,
+        threads: usize,
+        passes: &[u32],
+    ) -> Mandelbrot<u8> {
+        let mut config = config((48, 32));
+        config.color_fn = Box::new(outside_white);
+        config.supersampling = supersampling;
+        config.adaptive_threshold = adaptive_threshold;
+        config.threads = threads;
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        for iterations in passes {
+            mandelbrot.run_iterations(*iterations);
+        }
+
+        mandelbrot
+    }
+
+    #[test]
+    fn supersampling() {
+        let mandelbrot = supersampled(Supersampling::Grid(3), None, 4, &[100]);
+        let (pixels, orbits) = (mandelbrot.get_pixels(), mandelbrot.get_orbits());
+        assert_eq!(orbits.dimensions(), (48 * 9, 32));
+
+        // Each pixel is the average of its samples:
+        let mut edges = 0;
+        for (y, row) in orbits.rows().enumerate() {
+            for (x, samples) in row.chunks(9).enumerate() {
+                let info = RenderInfo {
+                    max_iterations: 100,
+                    escape_radius: 2.0,
+                    degree: 2.0,
+                    pixel_size: 0.0,
+                    histogram: None,
+                    stats: FrameStats::default(),
+                };
+                let colors: Vec<_> = samples.iter().map(|o| outside_white(o, &info)).collect();
+                let pixel = &pixels[(x as u32, y as u32)];
+
+                assert_eq!(*pixel, sampling::average(&colors));
+                if pixel.get_slice()[0] != 0 && pixel.get_slice()[0] != 255 {
+                    edges += 1;
+                }
+            }
+        }
+        assert!(edges > 50);
+
+        // The samples of a grid surround the pixel's point:
+        let single = supersampled(Supersampling::None, None, 1, &[100]);
+        let middle = supersampled(Supersampling::Grid(3), None, 1, &[100]);
+        for (a, b) in single
+            .get_orbits()
+            .iter()
+            .zip(middle.get_orbits().iter().skip(4).step_by(9))
+        {
+            assert_eq!(a.iterations, b.iterations);
+        }
+
+        // Jittered samples are reproducible:
+        let jittered =
+            |seed| supersampled(Supersampling::Jittered { size: 2, seed }, None, 4, &[100]);
+        assert_eq!(jittered(5).get_pixels(), jittered(5).get_pixels());
+        assert_ne!(jittered(5).get_pixels(), jittered(6).get_pixels());
+
+        for (sampling, threshold) in [
+            (Supersampling::Grid(0), None),
+            (Supersampling::RotatedGrid(2), Some(1.5)),
+            (Supersampling::RotatedGrid(2), Some(f64::NAN)),
+        ]
+        .iter()
+        {
+            let mut config = config((48, 32));
+            config.supersampling = *sampling;
+            config.adaptive_threshold = *threshold;
+            assert!(matches!(
+                Mandelbrot::new(config),
+                Err(Error::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn adaptive_supersampling() {
+        let grid = Supersampling::RotatedGrid(2);
+        let adaptive = supersampled(grid, Some(0.5), 1, &[20, 80]);
+        let full = supersampled(grid, None, 1, &[100]);
+        let (pixels, orbits) = (adaptive.get_pixels(), adaptive.get_orbits());
+        assert_eq!(orbits.dimensions(), (48 * 5, 32));
+
+        // Only the pixels around the edge of the set get supersampled:
+        let refined = orbits
+            .rows()
+            .flat_map(|row| row.chunks(5))
+            .filter(|samples| !samples[1].dormant)
+            .count();
+        assert!(0 < refined && refined < 48 * 32 / 2);
+
+        let single = supersampled(Supersampling::None, None, 1, &[100]);
+        for (y, row) in orbits.rows().enumerate() {
+            for (x, samples) in row.chunks(5).enumerate() {
+                let pixel = (x as u32, y as u32);
+
+                if samples[1].dormant {
+                    assert_eq!(pixels[pixel], single.get_pixels()[pixel]);
+                    continue;
+                }
+
+                // Samples that were woken up after the first pass caught up:
+                let full = &full.get_orbits().row(y as u32)[x * 4..x * 4 + 4];
+                for (a, b) in samples[1..].iter().zip(full.iter()) {
+                    assert_eq!(a.iterations, b.iterations);
+                }
+            }
+        }
+
+        // Threads don't change anything:
+        let threaded = supersampled(grid, Some(0.5), 4, &[20, 80]);
+        assert_eq!(threaded.get_pixels(), pixels);
+
+        // After a reset, it starts over:
+        let mut reset = supersampled(grid, Some(0.5), 1, &[20]);
+        reset.reset();
+        reset.run_iterations(20);
+        reset.run_iterations(80);
+        assert_eq!(reset.get_pixels(), pixels);
+    }
+
+    #[test]
+    fn histogram_coloring() {
+        let mut config = config((120, 80));
+        config.histogram = true;
+        let scale = HistogramColorScale::new(0.0, 1.0, 1.0, 300.0, false).unwrap();
+        config.color_fn = Box::new(scale);
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        let ranks = |mandelbrot: &Mandelbrot<u8>, max_iterations: u32| -> Vec<f64> {
+            let histogram = Histogram::new(mandelbrot.get_orbits().iter(), max_iterations);
+            mandelbrot
+                .get_orbits()
+                .iter()
+                .map(|orbit| histogram.rank_of(orbit))
+                .collect()
+        };
+
+        mandelbrot.run_iterations(50);
+        let (before, escaped): (Vec<f64>, Vec<bool>) = (
+            ranks(&mandelbrot, 50),
+            mandelbrot
+                .get_orbits()
+                .iter()
+                .map(|o| o.iterations < 50)
+                .collect(),
+        );
+
+        // Four times as many iterations barely moves the ranks of the points
+        // that had already escaped:
+        mandelbrot.run_iterations(150);
+        let after = ranks(&mandelbrot, 200);
+        let moved = before
+            .iter()
+            .zip(after.iter())
+            .zip(escaped.iter())
+            .filter(|(_, escaped)| **escaped)
+            .map(|((a, b), _)| (a - b).abs())
+            .fold(0.0, f64::max);
+        assert!(moved < 0.05, "{}", moved);
+
+        // And the pixels are colored with the histogram from the last pass:
+        let histogram = Histogram::new(mandelbrot.get_orbits().iter(), 200);
+        let info = RenderInfo {
+            max_iterations: 200,
+            escape_radius: 2.0,
+            degree: 2.0,
+            pixel_size: 0.0,
+            histogram: Some(&histogram),
+            stats: FrameStats::default(),
+        };
+        for (pixel, orbit) in mandelbrot
+            .get_pixels()
+            .iter()
+            .zip(mandelbrot.get_orbits().iter())
+        {
+            assert_eq!(*pixel, scale.pixel_color(orbit, &info));
+        }
+
+        assert!(HistogramColorScale::new(0.0, 1.0, 1.0, f64::NAN, true).is_err());
+    }
+
+    #[test]
+    fn smooth_histogram_coloring() {
+        for escape_radius in [2.0, 1e10].iter() {
+            let mut config = config((120, 80));
+            config.histogram = true;
+            config.escape_radius = *escape_radius;
+            config.color_fn =
+                Box::new(HistogramColorScale::new(0.0, 1.0, 1.0, 120.0, true).unwrap());
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(50);
+
+            let ranks: Vec<f64> = mandelbrot
+                .get_pixels()
+                .iter()
+                .zip(mandelbrot.get_orbits().iter())
+                .filter(|(_, orbit)| orbit.iterations < 50)
+                .map(|(pixel, _)| pixel.to_hsv().0 / 120.0)
+                .collect();
+            let (min, max) = (
+                ranks.iter().cloned().fold(1.0, f64::min),
+                ranks.iter().cloned().fold(0.0, f64::max),
+            );
+            let mean = ranks.iter().sum::<f64>() / ranks.len() as f64;
+
+            // The ranks cover the whole range, about evenly, at either
+            // radius (at a radius of 2, even the corners of the view don't
+            // overshoot far enough on their first iteration to quite reach
+            // 0):
+            assert!(min < 0.15, "{}: {}", escape_radius, min);
+            assert!(max > 0.98, "{}: {}", escape_radius, max);
+            assert!((mean - 0.5).abs() < 0.1, "{}: {}", escape_radius, mean);
+        }
+    }
+
+    #[test]
+    fn gradient_coloring() {
+        let mut gradient = Gradient::evenly_spaced(&[
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+        ])
+        .unwrap();
+        gradient.wrap = Wrap::Mirror;
+        gradient.density = 0.1;
+
+        let mut config = config((60, 40));
+        config.color_fn = Box::new(GradientColorScale::new(gradient.clone()));
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(50);
+
+        // Points that escaped get the color at their smoothed iteration count
+        // and points in the set are black:
+        for (pixel, orbit) in mandelbrot
+            .get_pixels()
+            .iter()
+            .zip(mandelbrot.get_orbits().iter())
+        {
+            let expected = if orbit.iterations == 50 {
+                Pixel::new(0, 0, 0)
+            } else {
+                let smooth = f64::from(orbit.iterations) + 1.0 - orbit.z.abs().log(10.0).log2();
+                gradient.pixel_at(smooth)
+            };
+
+            assert_eq!(*pixel, expected);
+        }
+
+        // The default gradient works for other pixel types too:
+        let info = RenderInfo {
+            max_iterations: 50,
+            escape_radius: 2.0,
+            degree: 2.0,
+            pixel_size: 0.0,
+            histogram: None,
+            stats: FrameStats::default(),
+        };
+        let escaped = mandelbrot.get_orbits().iter().find(|o| o.iterations < 50);
+        let pixel: Pixel<u16> = GradientColorScale::default().pixel_color(escaped.unwrap(), &info);
+        assert_ne!(pixel, Pixel::new(0, 0, 0));
+    }
+
+    #[test]
+    fn discrete_bands() {
+        let (red, green, blue) = (
+            Pixel::new(255u8, 0, 0),
+            Pixel::new(0, 255, 0),
+            Pixel::new(0, 0, 255),
+        );
+        let bands = |thresholds: BandThresholds, at: (f64, f64)| {
+            DiscreteColorScale::new(
+                thresholds,
+                vec![(at.0, red.clone()), (at.1, green.clone())],
+                blue.clone(),
+            )
+        };
+
+        let orbits: Vec<Orbit> = [1, 2, 2, 3, 5, 8, 10]
+            .iter()
+            .map(|i| Orbit {
+                iterations: *i,
+                ..Default::default()
+            })
+            .collect();
+        let histogram = Histogram::new(&orbits, 10);
+        let info = RenderInfo {
+            max_iterations: 10,
+            escape_radius: 2.0,
+            degree: 2.0,
+            pixel_size: 0.0,
+            histogram: Some(&histogram),
+            stats: FrameStats::default(),
+        };
+        let colors = |scale: &DiscreteColorScale<u8>| -> Vec<Pixel<u8>> {
+            orbits.iter().map(|o| scale.pixel_color(o, &info)).collect()
+        };
+
+        // Points get the first band they're below; the 10 (in the set) is
+        // past all of them:
+        let by_ratio = bands(BandThresholds::Ratio, (0.2, 0.5)).unwrap();
+        assert_eq!(
+            colors(&by_ratio),
+            vec![
+                red.clone(),
+                green.clone(),
+                green.clone(),
+                green.clone(),
+                blue.clone(),
+                blue.clone(),
+                blue.clone()
+            ]
+        );
+
+        let by_iterations = bands(BandThresholds::Iterations, (3.0, 9.0)).unwrap();
+        assert_eq!(
+            colors(&by_iterations)[2..5],
+            [red.clone(), green.clone(), green.clone()]
+        );
+
+        // Of the 6 points that escaped, half took 2 iterations or fewer (so
+        // the 2s are at the 50th percentile, which doesn't go past 50):
+        let by_percentile = bands(BandThresholds::Percentile, (50.0, 90.0)).unwrap();
+        assert_eq!(
+            colors(&by_percentile),
+            vec![
+                red.clone(),
+                red.clone(),
+                red.clone(),
+                green.clone(),
+                green.clone(),
+                blue.clone(),
+                blue.clone()
+            ]
+        );
+
+        // A last band ending at 100 takes in everything that escaped, even
+        // the points that took the most iterations:
+        let escaped: Vec<Orbit> = [1, 2, 3, 4, 10]
+            .iter()
+            .map(|i| Orbit {
+                iterations: *i,
+                ..Default::default()
+            })
+            .collect();
+        let histogram = Histogram::new(&escaped, 10);
+        let to_100 = bands(BandThresholds::Percentile, (50.0, 100.0)).unwrap();
+        let colors_with = |histogram: Option<&Histogram>| -> Vec<Pixel<u8>> {
+            let info = RenderInfo { histogram, ..info };
+            escaped
+                .iter()
+                .map(|o| to_100.pixel_color(o, &info))
+                .collect()
+        };
+        assert_eq!(
+            colors_with(Some(&histogram)),
+            vec![
+                red.clone(),
+                red.clone(),
+                green.clone(),
+                green.clone(),
+                blue.clone()
+            ]
+        );
+
+        // Without a histogram, the ratio (as a percentage) stands in:
+        assert_eq!(
+            colors_with(None),
+            vec![
+                red.clone(),
+                red.clone(),
+                red.clone(),
+                red.clone(),
+                blue.clone()
+            ]
+        );
+
+        // The default bands don't repeat a color in a row:
+        let default = DiscreteColorScale::<u8>::default();
+        assert!(default
+            .bands()
+            .windows(2)
+            .all(|pair| pair[0].1 != pair[1].1));
+
+        // Unsorted, overlapping and out of range bands are turned away:
+        let invalid = |thresholds: BandThresholds, at: (f64, f64)| match bands(thresholds, at) {
+            Err(Error::InvalidConfig(_)) => {}
+            other => panic!("{:?}", other),
+        };
+        invalid(BandThresholds::Ratio, (0.5, 0.2));
+        invalid(BandThresholds::Ratio, (0.5, 0.5));
+        invalid(BandThresholds::Ratio, (0.5, 1.5));
+        invalid(BandThresholds::Percentile, (50.0, 101.0));
+        invalid(BandThresholds::Iterations, (-1.0, 5.0));
+        invalid(BandThresholds::Iterations, (f64::NAN, 5.0));
+    }
+
+    #[test]
+    fn recoloring() {
+        let render = |color_fn: Box<dyn ColorScale<u8>>| {
+            let mut config = config((40, 30));
+            config.color_fn = color_fn;
+            config.supersampling = Supersampling::Grid(2);
+
+            let mut mandelbrot = Mandelbrot::new(config).unwrap();
+            mandelbrot.run_iterations(50);
+            mandelbrot
+        };
+
+        let mut mandelbrot = render(Box::<ContinuousColorScale>::default());
+        let discrete = render(Box::<DiscreteColorScale<u8>>::default());
+        let (pixels, orbits) = (
+            mandelbrot.get_pixels().clone(),
+            mandelbrot.get_orbits().clone(),
+        );
+
+        // Coloring with another scale comes out the same as running the
+        // iterations with it, and doesn't touch the orbits or the pixels:
+        let recolored = mandelbrot.render_with(&DiscreteColorScale::default());
+        assert_eq!(recolored, *discrete.get_pixels());
+        assert_ne!(recolored, pixels);
+        assert_eq!(*mandelbrot.get_pixels(), pixels);
+        assert_eq!(*mandelbrot.get_orbits(), orbits);
+
+        mandelbrot.set_color_fn(Box::<DiscreteColorScale<u8>>::default());
+        assert_eq!(*mandelbrot.get_pixels(), recolored);
+        assert_eq!(*mandelbrot.get_orbits(), orbits);
+
+        // Each frame of palette cycling is shifted a quarter of the way
+        // around the gradient from the last one:
+        let mut gradient = Gradient::evenly_spaced(&[
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 0.0, 0.0),
+        ])
+        .unwrap();
+        gradient.wrap = Wrap::Repeat;
+        gradient.density = 0.05;
+
+        let frames = mandelbrot.cycle_colors(&gradient, 4);
+        assert_eq!(frames.len(), 4);
+        for (i, frame) in frames.iter().enumerate() {
+            let mut shifted = gradient.clone();
+            shifted.offset = i as f64 / 4.0;
+
+            assert_eq!(
+                *frame,
+                mandelbrot.render_with(&GradientColorScale::new(shifted))
+            );
+        }
+        assert!(frames.windows(2).all(|pair| pair[0] != pair[1]));
+
+        // Mirrored gradients go around every 2:
+        gradient.wrap = Wrap::Mirror;
+        let frames = mandelbrot.cycle_colors(&gradient, 2);
+        gradient.offset = 1.0;
+        assert_eq!(
+            frames[1],
+            mandelbrot.render_with(&GradientColorScale::new(gradient))
+        );
+        assert_eq!(*mandelbrot.get_orbits(), orbits);
+    }
+
+    /// Paints every pixel the same gray: brighter the more of the frame
+    /// escaped
+    struct Coverage {
+        alpha: u8,
+    }
+
+    impl ColorScale<u8> for Coverage {
+        fn pixel_color(&self, _: &Orbit, info: &RenderInfo) -> Pixel<u8> {
+            let gray = (info.stats.escaped_fraction() * 255.0).round() as u8;
+            Pixel::new_rgba(gray, gray, gray, self.alpha)
+        }
+    }
+
+    #[test]
+    fn frame_stats() {
+        let orbits: Vec<Orbit> = [3, 10, 7, 10]
+            .iter()
+            .map(|i| Orbit {
+                iterations: *i,
+                ..Default::default()
+            })
+            .collect();
+        let stats = FrameStats::new(&orbits, 10);
+
+        assert_eq!((stats.points, stats.escaped), (4, 2));
+        assert_eq!(
+            (stats.min_escape_iterations, stats.max_escape_iterations),
+            (3, 7)
+        );
+        assert_eq!(stats.escaped_fraction(), 0.5);
+        assert_eq!(FrameStats::new(&[], 10), FrameStats::default());
+        assert_eq!(FrameStats::default().escaped_fraction(), 0.0);
+
+        // Color scales see the stats for the whole frame (and can carry
+        // settings of their own):
+        let mut config = config((60, 40));
+        config.color_fn = Box::new(Coverage { alpha: 200 });
+
+        let mut mandelbrot = Mandelbrot::new(config).unwrap();
+        mandelbrot.run_iterations(50);
+
+        let stats = FrameStats::new(mandelbrot.get_orbits().iter(), 50);
+        let gray = (stats.escaped_fraction() * 255.0).round() as u8;
+        assert!(0 < stats.escaped && stats.escaped < stats.points);
+        assert!(mandelbrot
+            .get_pixels()
+            .iter()
+            .all(|pixel| *pixel == Pixel::new_rgba(gray, gray, gray, 200)));
+
+        // Boxed color scales are color scales too:
+        let boxed: Box<dyn ColorScale<u8>> = Box::new(Coverage { alpha: 1 });
+        let exterior = InteriorColoring::Black.with_exterior(0.0, boxed).unwrap();
+        let info = RenderInfo {
+            max_iterations: 10,
+            escape_radius: 2.0,
+            degree: 2.0,
+            pixel_size: 0.0,
+            histogram: None,
+            stats,
+        };
+        assert_eq!(
+            exterior.pixel_color(&orbits[0], &info),
+            Pixel::new_rgba(gray, gray, gray, 1)
+        );
+    }
 }
@@ -0,0 +1,672 @@
+//! Palette Files
+//!
+//! Loaders for the palette files other fractal programs save, which all come
+//! back as `Gradient`s (so they can be handed to `GradientColorScale::new` to
+//! make a color scale):
+//!   - Fractint `.map` files: one color per line, as red, green and blue
+//!     values from 0 to 255 (anything after those on a line is a comment)
+//!   - Ultra Fractal `.ugr` files: any number of named gradients with colors
+//!     at indices from 0 to 399 that wrap around
+//!   - GIMP `.ggr` files: segments with a color at each end, a midpoint and
+//!     a blending function
+//!
+//! Files that don't parse give back an `Error::InvalidPalette` that says
+//! which line the problem is on.
+
+use error::{Error, Result};
+use gradient::{Color, Gradient, Interpolation, Wrap};
+use pixel::{hsv_to_rgb, rgb_to_hsv};
+use std::{f64::consts::PI, ffi::OsStr, fs, path::Path, result};
+
+/// Loads a palette file, picking the format by the file's extension (for
+/// `.ugr` files, which can hold more than one gradient, the first gradient is
+/// used)
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Gradient> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+
+    let parse: fn(&str) -> Result<Gradient> = match extension.as_deref() {
+        Some("map") => from_map,
+        Some("ggr") => from_ggr,
+        Some("ugr") => |text| Ok(from_ugr(text)?.remove(0).1),
+        _ => {
+            return Err(Error::Parse {
+                input: path.display().to_string(),
+                expected: "a palette file name (ending in .map, .ugr or .ggr)",
+            })
+        }
+    };
+
+    let text = fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+
+    parse(&text)
+}
+
+/// Parses a Fractint `.map` file
+///
+/// Fractint colors a point with iteration count `n` with color `n` (wrapping
+/// around past the last color), so the gradient that comes back repeats and
+/// has a density that moves it along one color per iteration.
+pub fn from_map(text: &str) -> Result<Gradient> {
+    let mut colors = Vec::new();
+
+    for (number, line) in numbered(text) {
+        let mut values = line.split_whitespace().take(3).map(str::parse::<u8>);
+
+        match (values.next(), values.next(), values.next()) {
+            (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => colors.push(Color::from_rgb8(r, g, b)),
+            _ => {
+                return Err(invalid(
+                    number,
+                    "expected a red, a green and a blue value from 0 to 255",
+                ))
+            }
+        }
+    }
+
+    if colors.is_empty() {
+        return Err(invalid(last_line(text), "the file doesn't have any colors"));
+    }
+
+    let count = colors.len() as f64;
+    let first = colors[0];
+    let mut stops: Vec<(f64, Color)> = colors
+        .into_iter()
+        .enumerate()
+        .map(|(i, color)| (i as f64 / count, color))
+        .collect();
+    stops.push((1.0, first));
+
+    let mut gradient = Gradient::new(stops)?;
+    gradient.wrap = Wrap::Repeat;
+    gradient.density = 1.0 / count;
+
+    Ok(gradient)
+}
+
+/// Parses a GIMP `.ggr` file
+///
+/// Segments with linear blending in RGB come through as they are; the other
+/// blending functions (curved, sine, sphere) and segments blended in HSV are
+/// approximated with a stop every 1/32nd of the segment. Colors set to
+/// follow GIMP's foreground or background color use the colors saved in the
+/// file. The gradient that comes back mirrors.
+pub fn from_ggr(text: &str) -> Result<Gradient> {
+    let end = last_line(text);
+    let mut lines = numbered(text);
+
+    match lines.next() {
+        Some((_, "GIMP Gradient")) => {}
+        _ => return Err(invalid(1, "GIMP gradients start with `GIMP Gradient`")),
+    }
+
+    let mut line = lines.next();
+    if let Some((_, name)) = line {
+        if name.starts_with("Name:") {
+            line = lines.next();
+        }
+    }
+
+    let count = match line {
+        Some((number, count)) => match count.parse::<usize>() {
+            Ok(0) => return Err(invalid(number, "a gradient needs at least one segment")),
+            Ok(count) => count,
+            Err(_) => return Err(invalid(number, "expected the number of segments")),
+        },
+        None => return Err(invalid(end, "expected the number of segments")),
+    };
+
+    let mut stops: Vec<(f64, Color)> = Vec::new();
+    for _ in 0..count {
+        let (number, line) = lines
+            .next()
+            .ok_or_else(|| invalid(end, "there are fewer segments than the file says"))?;
+
+        let segment = segment(line).map_err(|reason| invalid(number, reason))?;
+        if stops.last().is_some_and(|last| last.0 > segment[0].0) {
+            return Err(invalid(number, "segments have to be in order"));
+        }
+
+        stops.extend(segment);
+    }
+
+    if let Some((number, _)) = lines.next() {
+        return Err(invalid(
+            number,
+            "there are more segments than the file says",
+        ));
+    }
+
+    let mut gradient = Gradient::new(stops)?;
+    gradient.wrap = Wrap::Mirror;
+    gradient.density = 1.0 / 24.0;
+
+    Ok(gradient)
+}
+
+/// Parses an Ultra Fractal `.ugr` file, giving back each gradient in it with
+/// its name
+///
+/// Only the colors are used: opacity and the other settings (like
+/// `smooth=yes`, which Ultra Fractal draws with curves instead of straight
+/// blends) are skipped. The gradients that come back repeat, going through
+/// all 400 indices every 24 iterations.
+pub fn from_ugr(text: &str) -> Result<Vec<(String, Gradient)>> {
+    let mut gradients = Vec::new();
+    let mut entry: Option<UgrEntry> = None;
+    let mut colors_section = false;
+
+    for (number, line) in numbered(text) {
+        if line.starts_with(';') {
+            continue;
+        }
+
+        let current = match entry {
+            Some(ref mut current) => current,
+            None => match line.strip_suffix('{') {
+                Some(name) => {
+                    entry = Some(UgrEntry {
+                        name: name.trim().to_string(),
+                        stops: Vec::new(),
+                    });
+                    colors_section = false;
+                    continue;
+                }
+                None => return Err(invalid(number, "expected a gradient name followed by `{`")),
+            },
+        };
+
+        if line == "}" {
+            let current = entry.take().expect("there's a gradient open");
+            gradients.push((current.name.clone(), current.finish(number)?));
+        } else if line.ends_with('{') {
+            return Err(invalid(number, "a gradient is missing its closing `}`"));
+        } else if line.ends_with(':') {
+            colors_section = line == "gradient:";
+        } else if colors_section {
+            for setting in settings(line) {
+                let (key, value) = setting.map_err(|reason| invalid(number, reason))?;
+                current
+                    .set(key, value)
+                    .map_err(|reason| invalid(number, reason))?;
+            }
+        }
+    }
+
+    if entry.is_some() {
+        return Err(invalid(
+            last_line(text),
+            "a gradient is missing its closing `}`",
+        ));
+    }
+
+    if gradients.is_empty() {
+        return Err(invalid(
+            last_line(text),
+            "the file doesn't have any gradients",
+        ));
+    }
+
+    Ok(gradients)
+}
+
+/// The number of indices in an Ultra Fractal gradient
+const UGR_INDICES: u32 = 400;
+
+/// How many stops the segments of GIMP gradients that can't be copied over
+/// exactly are split into
+const GGR_SAMPLES: u32 = 32;
+
+/// A gradient in a `.ugr` file that's still being read
+struct UgrEntry {
+    name: String,
+    /// Indices and their colors, in the order they're in in the file
+    stops: Vec<(u32, Option<Color>)>,
+}
+
+impl UgrEntry {
+    fn set(&mut self, key: &str, value: &str) -> result::Result<(), &'static str> {
+        match key {
+            "index" => match value.parse::<u32>() {
+                Ok(index) if index < UGR_INDICES => self.stops.push((index, None)),
+                _ => return Err("indices have to be whole numbers from 0 to 399"),
+            },
+            "color" => {
+                let color = match value.parse::<u32>() {
+                    Ok(color) if color <= 0xFF_FFFF => color,
+                    _ => return Err("colors have to be whole numbers from 0 to 16777215"),
+                };
+
+                // Colors are saved as blue, green and red bytes:
+                let color = Color::from_rgb8(color as u8, (color >> 8) as u8, (color >> 16) as u8);
+                match self.stops.last_mut() {
+                    Some(stop) => stop.1 = Some(color),
+                    None => return Err("a `color` has to come after an `index`"),
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Makes the gradient (`line` is the line the gradient ends on)
+    fn finish(mut self, line: usize) -> Result<Gradient> {
+        if self.stops.iter().any(|(_, color)| color.is_none()) {
+            return Err(invalid(line, "every `index` needs a `color`"));
+        }
+
+        self.stops.sort_by_key(|(index, _)| *index);
+        let stops: Vec<(f64, Color)> = self
+            .stops
+            .iter()
+            .map(|(index, color)| {
+                let color = color.expect("every index has a color");
+                (f64::from(*index) / f64::from(UGR_INDICES), color)
+            })
+            .collect();
+
+        let (first, last) = match (stops.first(), stops.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(invalid(line, "a gradient needs at least one color")),
+        };
+
+        // The colors wrap around from the last index back to the first, so
+        // both ends get the color where that blend crosses 0:
+        let span = first.0 + 1.0 - last.0;
+        let wrapped = if span > 0.0 {
+            let f = (1.0 - last.0) / span;
+            Color::new(
+                last.1.r + (first.1.r - last.1.r) * f,
+                last.1.g + (first.1.g - last.1.g) * f,
+                last.1.b + (first.1.b - last.1.b) * f,
+            )
+        } else {
+            first.1
+        };
+
+        let mut gradient = Gradient::new(
+            Some((0.0, wrapped))
+                .into_iter()
+                .chain(stops)
+                .chain(Some((1.0, wrapped)))
+                .collect(),
+        )?;
+        gradient.interpolation = Interpolation::Rgb;
+        gradient.wrap = Wrap::Repeat;
+        gradient.density = 1.0 / 24.0;
+
+        Ok(gradient)
+    }
+}
+
+/// The stops for one segment of a GIMP gradient
+fn segment(line: &str) -> result::Result<Vec<(f64, Color)>, &'static str> {
+    let values = line
+        .split_whitespace()
+        .map(str::parse::<f64>)
+        .collect::<result::Result<Vec<f64>, _>>()
+        .map_err(|_| "segments have to be made of numbers")?;
+
+    if values.len() != 13 && values.len() != 15 {
+        return Err("segments have to have 13 or 15 numbers");
+    }
+
+    let (left, middle, right) = (values[0], values[1], values[2]);
+    if !(0.0 <= left && left <= middle && middle <= right && right <= 1.0) {
+        return Err("segment positions have to be in order, from 0 to 1");
+    }
+
+    if values[3..11].iter().any(|v| !(0.0..=1.0).contains(v)) {
+        return Err("colors have to be between 0 and 1");
+    }
+
+    let from = Color::new_rgba(values[3], values[4], values[5], values[6]);
+    let to = Color::new_rgba(values[7], values[8], values[9], values[10]);
+
+    // Both of these are indices into a list of kinds; anything that isn't a
+    // whole number in range isn't one:
+    let index = |value: f64, count: usize| {
+        if value.fract() == 0.0 && value >= 0.0 && value < count as f64 {
+            Some(value as usize)
+        } else {
+            None
+        }
+    };
+
+    let blendings = [
+        Blending::Linear,
+        Blending::Curved,
+        Blending::Sine,
+        Blending::SphereIncreasing,
+        Blending::SphereDecreasing,
+        Blending::Step,
+    ];
+    let blending = index(values[11], blendings.len())
+        .map(|i| blendings[i])
+        .ok_or("unknown blending function")?;
+
+    let colorings = [
+        Coloring::Rgb,
+        Coloring::HsvCounterClockwise,
+        Coloring::HsvClockwise,
+    ];
+    let coloring = index(values[12], colorings.len())
+        .map(|i| colorings[i])
+        .ok_or("unknown coloring type")?;
+
+    let width = right - left;
+    let middle_fraction = if width > 0.0 {
+        (middle - left) / width
+    } else {
+        0.5
+    };
+
+    Ok(match (blending, coloring) {
+        (Blending::Linear, Coloring::Rgb) => vec![
+            (left, from),
+            (middle, coloring.blend(from, to, 0.5)),
+            (right, to),
+        ],
+        (Blending::Step, _) => vec![(left, from), (middle, from), (middle, to), (right, to)],
+        _ => (0..=GGR_SAMPLES)
+            .map(|i| {
+                let p = f64::from(i) / f64::from(GGR_SAMPLES);
+                let f = blending.factor(p, middle_fraction);
+                (left + width * p, coloring.blend(from, to, f))
+            })
+            .collect(),
+    })
+}
+
+/// How GIMP blends from one end of a segment to the other
+#[derive(Clone, Copy)]
+enum Blending {
+    Linear,
+    Curved,
+    Sine,
+    SphereIncreasing,
+    SphereDecreasing,
+    Step,
+}
+
+impl Blending {
+    /// How far along from the left color to the right one position `p` of the
+    /// way through a segment is, where `middle` (also a fraction of the
+    /// segment) is where it's half way
+    fn factor(self, p: f64, middle: f64) -> f64 {
+        let linear = || {
+            if p <= middle {
+                if middle > 0.0 {
+                    0.5 * p / middle
+                } else {
+                    0.0
+                }
+            } else if middle < 1.0 {
+                0.5 + 0.5 * (p - middle) / (1.0 - middle)
+            } else {
+                1.0
+            }
+        };
+
+        match self {
+            Blending::Linear => linear(),
+            Blending::Curved => p.powf(0.5f64.ln() / middle.max(1e-10).ln()),
+            Blending::Sine => ((PI * linear() - PI / 2.0).sin() + 1.0) / 2.0,
+            Blending::SphereIncreasing => (1.0 - (linear() - 1.0).powi(2)).sqrt(),
+            Blending::SphereDecreasing => 1.0 - (1.0 - linear().powi(2)).sqrt(),
+            Blending::Step => {
+                if p >= middle {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// The color space GIMP blends a segment's colors in
+#[derive(Clone, Copy)]
+enum Coloring {
+    Rgb,
+    HsvCounterClockwise,
+    HsvClockwise,
+}
+
+impl Coloring {
+    fn blend(self, from: Color, to: Color, f: f64) -> Color {
+        let lerp = |a: f64, b: f64| a + (b - a) * f;
+        let alpha = lerp(from.a, to.a);
+
+        let (r, g, b) = match self {
+            Coloring::Rgb => (lerp(from.r, to.r), lerp(from.g, to.g), lerp(from.b, to.b)),
+            _ => {
+                let ((h1, s1, v1), (h2, s2, v2)) = (
+                    rgb_to_hsv(from.r, from.g, from.b),
+                    rgb_to_hsv(to.r, to.g, to.b),
+                );
+
+                // Counterclockwise is increasing hue:
+                let turn = match self {
+                    Coloring::HsvCounterClockwise => (h2 - h1).rem_euclid(360.0),
+                    _ => -(h1 - h2).rem_euclid(360.0),
+                };
+
+                hsv_to_rgb(h1 + turn * f, lerp(s1, s2), lerp(v1, v2))
+            }
+        };
+
+        Color::new_rgba(r, g, b, alpha)
+    }
+}
+
+/// The `key=value` settings on a line of a `.ugr` file (values can be in
+/// quotes, which are taken off)
+fn settings(line: &str) -> Vec<result::Result<(&str, &str), &'static str>> {
+    let mut settings = Vec::new();
+    let mut rest = line.trim_start();
+
+    while !rest.is_empty() {
+        let setting = match rest.find('=') {
+            Some(equals) if !rest[..equals].contains(char::is_whitespace) => {
+                let (key, value) = (&rest[..equals], &rest[equals + 1..]);
+
+                let (value, after) = match value.strip_prefix('"') {
+                    Some(quoted) => match quoted.find('"') {
+                        Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                        None => {
+                            settings.push(Err("a quote is missing its closing `\"`"));
+                            break;
+                        }
+                    },
+                    None => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+
+                rest = after.trim_start();
+                Ok((key, value))
+            }
+            _ => {
+                settings.push(Err("expected `key=value` settings"));
+                break;
+            }
+        };
+
+        settings.push(setting);
+    }
+
+    settings
+}
+
+/// The lines of a file that aren't blank, trimmed and numbered from 1
+fn numbered(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+/// The number of the last line of a file (for errors about things that are
+/// missing at the end)
+fn last_line(text: &str) -> usize {
+    text.lines().count().max(1)
+}
+
+fn invalid(line: usize, reason: &'static str) -> Error {
+    Error::InvalidPalette { line, reason }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use error::Error;
+    use gradient::{Color, Gradient, Wrap};
+    use palette::{self, from_ggr, from_map, from_ugr};
+    use pixel::{Pixel, PixelMath};
+
+    fn fixture(name: &str) -> String {
+        format!(
+            "{}/tests/assets/palettes/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        )
+    }
+
+    fn pixel(gradient: &Gradient, position: f64) -> Pixel<u8> {
+        let (offset, density) = (gradient.offset, gradient.density);
+        gradient.pixel_at((position - offset) / density)
+    }
+
+    fn error_line<T: ::std::fmt::Debug>(result: ::error::Result<T>) -> usize {
+        match result {
+            Err(Error::InvalidPalette { line, .. }) => line,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn map_files() {
+        let gradient = palette::load(fixture("primaries.map")).unwrap();
+
+        // One color per iteration, wrapping around:
+        assert_eq!(gradient.wrap, Wrap::Repeat);
+        assert_eq!(gradient.pixel_at::<u8>(0.0), Pixel::new(255, 0, 0));
+        assert_eq!(gradient.pixel_at::<u8>(1.0), Pixel::new(0, 255, 0));
+        assert_eq!(gradient.pixel_at::<u8>(2.0), Pixel::new(0, 0, 255));
+        assert_eq!(gradient.pixel_at::<u8>(3.5), Pixel::new(255, 128, 128));
+        assert_eq!(gradient.pixel_at::<u8>(4.0), Pixel::new(255, 0, 0));
+
+        assert_eq!(error_line(from_map("0 0 0\n\n1 2\n")), 3);
+        assert_eq!(error_line(from_map("0 0 0\n256 0 0\n")), 2);
+        assert_eq!(error_line(from_map("red green blue")), 1);
+        assert_eq!(error_line(from_map("\n\n")), 2);
+    }
+
+    #[test]
+    fn ggr_files() {
+        let gradient = palette::load(fixture("segments.ggr")).unwrap();
+
+        // A linear segment with its midpoint moved over (to 0.05), so it's
+        // 60% of the way at 0.1 and 75% of the way at 0.175:
+        assert_eq!(pixel(&gradient, 0.0), Pixel::new(0, 0, 0));
+        assert_eq!(pixel(&gradient, 0.1), Pixel::new(153, 153, 153));
+        assert_eq!(pixel(&gradient, 0.175), Pixel::new(191, 191, 191));
+
+        // A step, then a trip around the color wheel (counterclockwise, so
+        // from red through yellow and green to blue):
+        assert_eq!(pixel(&gradient, 0.39), Pixel::new(255, 0, 0));
+        assert_eq!(pixel(&gradient, 0.41), Pixel::new(0, 0, 255));
+        assert_eq!(pixel(&gradient, 0.5), Pixel::new(255, 0, 0));
+        assert_eq!(pixel(&gradient, 0.625), Pixel::new(255, 255, 0));
+        assert_eq!(pixel(&gradient, 0.75), Pixel::new(0, 255, 0));
+        assert_eq!(pixel(&gradient, 1.0), Pixel::new(0, 0, 255));
+
+        let header = "GIMP Gradient\nName: Test\n";
+        let segment = "0 0.5 1 0 0 0 1 1 1 1 1 0 0";
+        let ggr = |rest: &str| from_ggr(&format!("{}{}", header, rest));
+
+        assert!(ggr(&format!("1\n{}\n", segment)).is_ok());
+        assert_eq!(error_line(from_ggr("GIMP Palette\n")), 1);
+        assert_eq!(error_line(ggr("one\n")), 3);
+        assert_eq!(error_line(ggr(&format!("2\n{}\n", segment))), 4);
+        assert_eq!(
+            error_line(ggr(&format!("1\n{}\n{}\n", segment, segment))),
+            5
+        );
+        assert_eq!(error_line(ggr("1\n0 0.5 1 0 0 0 1 1 1 1 1 0\n")), 4);
+        assert_eq!(error_line(ggr("1\n0 0.5 1 0 0 0 1 1 1 2 1 0 0\n")), 4);
+        assert_eq!(error_line(ggr("1\n0.5 0.2 1 0 0 0 1 1 1 1 1 0 0\n")), 4);
+        assert_eq!(error_line(ggr("1\n0 0.5 1 0 0 0 1 1 1 1 1 9 0\n")), 4);
+        assert_eq!(error_line(ggr("1\n0 0.5 1 0 0 0 1 1 1 1 1 0.5 0\n")), 4);
+        assert_eq!(error_line(ggr("1\n0 0.5 1 0 0 0 1 1 1 1 1 0 -1\n")), 4);
+        assert_eq!(error_line(ggr("1\n0 0.5 1 0 0 0 1 1 1 1 1 0 3\n")), 4);
+        assert_eq!(
+            error_line(ggr(
+                "2\n0.5 0.6 1 0 0 0 1 1 1 1 1 0 0\n0 0.1 0.5 0 0 0 1 1 1 1 1 0 0\n"
+            )),
+            5
+        );
+    }
+
+    #[test]
+    fn ugr_files() {
+        let gradients = from_ugr(&::std::fs::read_to_string(fixture("two.ugr")).unwrap()).unwrap();
+        let names: Vec<&str> = gradients.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Sunset", "Gray Ramp"]);
+
+        // The colors are stored as blue, green and red, and the blend wraps
+        // around from index 300 back to 100 (half way across, at 0):
+        let sunset = &gradients[0].1;
+        assert_eq!(pixel(sunset, 0.25), Pixel::new(255, 0, 0));
+        assert_eq!(pixel(sunset, 0.5), Pixel::new(255, 128, 0));
+        assert_eq!(pixel(sunset, 0.75), Pixel::new(0, 0, 255));
+        assert_eq!(pixel(sunset, 0.0), Pixel::new(128, 0, 128));
+        assert_eq!(pixel(sunset, 1.0), Pixel::new(128, 0, 128));
+
+        // `load` takes the first gradient in the file:
+        assert_eq!(palette::load(fixture("two.ugr")).unwrap(), *sunset);
+
+        let gray = &gradients[1].1;
+        assert_eq!(pixel(gray, 0.0), Pixel::new(0, 0, 0));
+        assert_eq!(pixel(gray, 0.5), Pixel::new(128, 128, 128));
+
+        let ugr = |settings: &str| from_ugr(&format!("x {{\ngradient:\n{}\n}}\n", settings));
+        assert!(ugr("index=0 color=0").is_ok());
+        assert_eq!(error_line(ugr("")), 4);
+        assert_eq!(error_line(ugr("index=400 color=0")), 3);
+        assert_eq!(error_line(ugr("color=0")), 3);
+        assert_eq!(error_line(ugr("index=0 color=red")), 3);
+        assert_eq!(error_line(ugr("index=0")), 4);
+        assert_eq!(error_line(ugr("title=\"unclosed")), 3);
+        assert_eq!(error_line(from_ugr("x {\ngradient:\nindex=0 color=0\n")), 3);
+        assert_eq!(error_line(from_ugr("gradient:\n")), 1);
+        assert_eq!(error_line(from_ugr("; just a comment\n")), 1);
+    }
+
+    #[test]
+    fn loading() {
+        match palette::load(fixture("missing.map")) {
+            Err(Error::Io { ref path, .. }) => assert!(path.ends_with("missing.map")),
+            other => panic!("{:?}", other),
+        }
+
+        match palette::load(fixture("primaries.txt")) {
+            Err(Error::Parse { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+
+        assert_eq!(
+            palette::load(fixture("primaries.map")).unwrap().stops()[0],
+            (0.0, Color::new(1.0, 0.0, 0.0))
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! Perturbation rendering, for deep zooms
+//!
+//! Past a zoom of around 1e13, `f64`s don't have enough precision to tell the
+//! coordinates of neighbouring pixels apart and renders turn into big blocks.
+//! Perturbation gets around this by calculating the orbit of a single
+//! _reference_ point at high precision and then, for every pixel, only keeping
+//! track of the (small) difference between the pixel's orbit and the reference
+//! orbit. Small differences are something `f64`s handle just fine.
+//!
+//! With `Z` as the reference orbit (for the reference point `C`), `z = Z + δ` as
+//! a pixel's orbit and `c = C + δc` as the pixel's coordinate:
+//!
+//! ```text
+//! z' = z² + c
+//!    = (Z + δ)² + C + δc
+//!    = (Z² + C) + 2Zδ + δ² + δc
+//!    = Z' + (2Zδ + δ² + δc)
+//!
+//! δ' = 2Zδ + δ² + δc
+//! ```
+//!
+//! This goes wrong ("glitches") when a pixel's orbit gets much closer to 0
+//! than the reference orbit does, at which point `δ` loses its precision.
+//! Pauldelbrot's criterion (`|Z + δ| < tolerance * |Z|`) is used to spot
+//! this; glitched pixels are _rebased_: the pixel's current value becomes its
+//! new `δ` and it carries on from the start of the reference orbit (where `Z`
+//! is 0). Pixels also get rebased if the reference orbit escapes before they
+//! do.
+//!
+//! For the first stretch of a deep zoom's orbit, every pixel's `δ` is tiny and
+//! behaves almost exactly like a polynomial in `δc`. _Series approximation_
+//! (see `SeriesApproximation`) finds that polynomial once, alongside the
+//! reference orbit, so every pixel can start that many iterations in.
+
+extern crate num_traits;
+
+use self::num_traits::Float;
+use big_float::{BigFloat, ParseBigFloatError};
+use complex_number::ComplexNumber;
+use std::cmp;
+
+/// The number of extra bits of precision reference points get on top of what
+/// it takes to tell neighbouring pixels apart
+const GUARD_BITS: u32 = 32;
+
+/// How many bits of precision a reference point needs for a view `width`
+/// wide that's `pixels` pixels across
+///
+/// Coordinates are (at most) around 2 in magnitude, so telling points
+/// `width / pixels` apart takes `log2(2 / (width / pixels))` bits, plus a few
+/// extra to keep rounding errors in the reference orbit at bay.
+pub fn bits_for_zoom(width: f64, pixels: u32) -> u32 {
+    let spacing = width / f64::from(pixels.max(1));
+
+    (4.0 / spacing).log2().ceil().max(0.0) as u32 + GUARD_BITS
+}
+
+/// A source of reference orbits for perturbation rendering
+pub trait Reference: Send {
+    /// Extends the reference orbit until it has `len` points in it or until
+    /// it escapes (gets further than the escape radius from the origin)
+    fn extend(&mut self, len: usize, escape_radius_sqr: f64);
+
+    /// The reference orbit so far (rounded to `f64`s), starting at 0
+    fn orbit(&self) -> &[ComplexNumber<f64>];
+}
+
+/// The orbit of a reference point, calculated with whatever precision the
+/// reference point's coordinates are stored in
+pub struct ReferenceOrbit<T: Float> {
+    /// The reference point
+    c: ComplexNumber<T>,
+    /// The last point in the orbit, at full precision
+    z: ComplexNumber<T>,
+    /// The orbit so far, rounded to `f64`s
+    points: Vec<ComplexNumber<f64>>,
+    /// Whether the orbit has escaped
+    escaped: bool,
+}
+
+impl<T: Float> ReferenceOrbit<T> {
+    /// Makes a new (empty) orbit for the reference point given
+    pub fn new(c: ComplexNumber<T>) -> ReferenceOrbit<T> {
+        ReferenceOrbit {
+            c,
+            z: ComplexNumber::new(T::zero(), T::zero()),
+            points: vec![ComplexNumber::new(0.0, 0.0)],
+            escaped: false,
+        }
+    }
+
+    /// The reference point
+    pub fn point(&self) -> ComplexNumber<T> {
+        self.c
+    }
+}
+
+impl<T: Float + Send> Reference for ReferenceOrbit<T> {
+    fn extend(&mut self, len: usize, escape_radius_sqr: f64) {
+        while !self.escaped && self.points.len() < len {
+            self.z = self.z * self.z + self.c;
+
+            let z = ComplexNumber::new(
+                self.z.real().to_f64().unwrap_or(f64::NAN),
+                self.z.imag().to_f64().unwrap_or(f64::NAN),
+            );
+
+            self.escaped = z.norm_sqr() >= escape_radius_sqr || z.norm_sqr().is_nan();
+            self.points.push(z);
+        }
+    }
+
+    fn orbit(&self) -> &[ComplexNumber<f64>] {
+        &self.points
+    }
+}
+
+/// A polynomial in `δc` that approximates `δ` for the first few iterations of
+/// every pixel in a view, so that those iterations can be skipped
+///
+/// `δ` starts out as 0 and, with `δ' = 2Zδ + δ² + δc`, it's always a
+/// polynomial in `δc`: `δₙ = A₁ₙδc + A₂ₙδc² + A₃ₙδc³ + ...` with
+///
+/// ```text
+/// A₁ₙ₊₁ = 2ZₙA₁ₙ + 1
+/// Aₖₙ₊₁ = 2ZₙAₖₙ + Σⱼ AⱼₙAₖ₋ⱼₙ   (j = 1..k-1)
+/// ```
+///
+/// Only the first few terms are kept. Alongside the coefficients, we keep an
+/// upper bound on how far the truncated series can be from the real `δ` for
+/// any `δc` in the view (the terms that were dropped, and how much the error
+/// so far can grow in one step). The series is used for as long as that bound
+/// stays a small fraction of the distance between neighbouring pixels' orbits
+/// and no pixel could have escaped yet.
+///
+/// The coefficients are stored scaled by `rᵏ` (`r` being the largest `|δc|` in
+/// the view) so that they stay around 1 in magnitude instead of overflowing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesApproximation {
+    /// The number of iterations the series covers
+    iterations: usize,
+    /// The (scaled) coefficients: `Aₖrᵏ`
+    coefficients: Vec<ComplexNumber<f64>>,
+    /// The largest `|δc|` the series is meant for
+    radius: f64,
+}
+
+impl SeriesApproximation {
+    /// Works out how many iterations of the reference orbit given can be
+    /// skipped with a series of `terms` terms, for pixels up to `radius` away
+    /// from the reference point and `spacing` apart
+    ///
+    /// The error bound has to stay below `tolerance` times the distance
+    /// between neighbouring pixels' orbits; no more than `max_iterations`
+    /// iterations are ever skipped.
+    pub fn new(
+        reference: &[ComplexNumber<f64>],
+        terms: usize,
+        radius: f64,
+        spacing: f64,
+        tolerance: f64,
+        escape_radius: f64,
+        max_iterations: usize,
+    ) -> SeriesApproximation {
+        let zero = ComplexNumber::new(0.0, 0.0);
+        let mut series = SeriesApproximation {
+            iterations: 0,
+            coefficients: vec![zero; terms],
+            radius,
+        };
+
+        if terms == 0 || radius <= 0.0 || radius.is_nan() {
+            return series;
+        }
+
+        let last = cmp::min(max_iterations, reference.len().saturating_sub(1));
+        let mut error = 0.0;
+        let mut next = vec![zero; terms];
+
+        for n in 0..last {
+            let z = reference[n];
+            let b = &series.coefficients;
+
+            // The product term (Σⱼ AⱼAₖ₋ⱼ), both for the terms we keep and (to
+            // bound the error) for the ones we drop:
+            let product = |k: usize| {
+                (cmp::max(1, k.saturating_sub(terms))..cmp::min(k, terms + 1))
+                    .fold(zero, |sum, j| sum + b[j - 1] * b[k - j - 1])
+            };
+
+            for k in 1..=terms {
+                next[k - 1] = z * b[k - 1] * 2.0 + product(k);
+            }
+            next[0] += radius;
+
+            let truncated: f64 = (terms + 1..=2 * terms).map(|k| product(k).abs()).sum();
+            let magnitude: f64 = b.iter().map(|c| c.abs()).sum();
+
+            error = (2.0 * z.abs() + 2.0 * magnitude + error) * error + truncated;
+
+            let next_magnitude: f64 = next.iter().map(|c| c.abs()).sum();
+            let separation = next[0].abs() * spacing / radius;
+
+            if error > tolerance * separation
+                || error.is_nan()
+                || reference[n + 1].abs() + next_magnitude + error >= escape_radius
+            {
+                break;
+            }
+
+            series.coefficients.copy_from_slice(&next);
+            series.iterations = n + 1;
+        }
+
+        series
+    }
+
+    /// The number of iterations the series skips
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// The approximate `δ` after `iterations()` iterations for a pixel `dc`
+    /// away from the reference point
+    pub fn evaluate(&self, dc: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        let u = dc / self.radius;
+
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(ComplexNumber::new(0.0, 0.0), |sum, c| (sum + *c) * u)
+    }
+
+    /// The derivative of `evaluate` with respect to `dc`, which is the
+    /// approximate `dz/dc` after `iterations()` iterations (for distance
+    /// estimation)
+    pub fn derivative(&self, dc: ComplexNumber<f64>) -> ComplexNumber<f64> {
+        let u = dc / self.radius;
+
+        self.coefficients
+            .iter()
+            .enumerate()
+            .rev()
+            .fold(ComplexNumber::new(0.0, 0.0), |sum, (k, c)| {
+                sum * u + *c * ((k + 1) as f64)
+            })
+            / self.radius
+    }
+}
+
+/// The settings for perturbation rendering
+///
+/// When a `Mandelbrot` is given one of these, its `Viewport` is taken to be
+/// _relative_ to the reference point (so `top_left` is the offset of the top
+/// left corner from the reference point).
+pub struct Perturbation {
+    /// Where the reference orbit comes from
+    pub reference: Box<dyn Reference>,
+    /// How small a pixel's orbit can get relative to the reference orbit
+    /// before we consider the pixel to be glitched (Pauldelbrot's criterion)
+    pub glitch_tolerance: f64,
+    /// The number of terms to use for series approximation (0 turns it off)
+    pub series_terms: usize,
+    /// How big the series approximation's error bound can get, as a fraction
+    /// of the distance between neighbouring pixels' orbits
+    pub series_tolerance: f64,
+}
+
+impl Perturbation {
+    /// Sets up perturbation rendering around the reference given, with the
+    /// default glitch tolerance (`1e-3`) and an 8 term series approximation
+    /// with a tolerance of `1e-3`
+    pub fn new<R: 'static + Reference>(reference: R) -> Perturbation {
+        Perturbation {
+            reference: Box::new(reference),
+            glitch_tolerance: 1e-3,
+            series_terms: 8,
+            series_tolerance: 1e-3,
+        }
+    }
+
+    /// Sets up perturbation rendering around the reference point given (as
+    /// decimal strings, so that no precision is lost), picking a precision
+    /// for the reference orbit that's enough for a view `width` wide and
+    /// `pixels` pixels across (see `bits_for_zoom`)
+    ///
+    /// Shallow zooms just use `f64`s; deeper zooms use a `BigFloat` that's
+    /// big enough.
+    pub fn at(
+        re: &str,
+        im: &str,
+        width: f64,
+        pixels: u32,
+    ) -> Result<Perturbation, ParseBigFloatError> {
+        let bits = bits_for_zoom(width, pixels);
+
+        Ok(match bits {
+            0..=53 => {
+                let c = big_point::<1>(re, im)?;
+                Perturbation::new(ReferenceOrbit::new(ComplexNumber::new(
+                    c.real().to_f64(),
+                    c.imag().to_f64(),
+                )))
+            }
+            54..=128 => big_reference::<2>(re, im)?,
+            129..=192 => big_reference::<3>(re, im)?,
+            193..=256 => big_reference::<4>(re, im)?,
+            257..=384 => big_reference::<6>(re, im)?,
+            385..=512 => big_reference::<8>(re, im)?,
+            513..=768 => big_reference::<12>(re, im)?,
+            769..=1024 => big_reference::<16>(re, im)?,
+            _ => big_reference::<32>(re, im)?,
+        })
+    }
+}
+
+fn big_point<const N: usize>(
+    re: &str,
+    im: &str,
+) -> Result<ComplexNumber<BigFloat<N>>, ParseBigFloatError> {
+    Ok(ComplexNumber::new(re.trim().parse()?, im.trim().parse()?))
+}
+
+fn big_reference<const N: usize>(re: &str, im: &str) -> Result<Perturbation, ParseBigFloatError> {
+    Ok(Perturbation::new(ReferenceOrbit::new(big_point::<N>(
+        re, im,
+    )?)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use complex_number::ComplexNumber;
+    use perturbation::{
+        bits_for_zoom, Perturbation, Reference, ReferenceOrbit, SeriesApproximation,
+    };
+
+    #[test]
+    fn reference_orbit() {
+        let mut reference = ReferenceOrbit::new(ComplexNumber::new(-1.0, 0.0));
+        reference.extend(5, 4.0);
+
+        let expected = [0.0, -1.0, 0.0, -1.0, 0.0];
+        assert_eq!(reference.orbit().len(), expected.len());
+
+        for (z, e) in reference.orbit().iter().zip(expected.iter()) {
+            assert_eq!(*z, ComplexNumber::new(*e, 0.0));
+        }
+
+        // Extending it should pick up where we left off:
+        reference.extend(7, 4.0);
+        assert_eq!(reference.orbit().len(), 7);
+        assert_eq!(reference.orbit()[6], ComplexNumber::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn precision_from_zoom() {
+        assert!(bits_for_zoom(4.0, 1000) <= 53);
+        assert!(bits_for_zoom(1e-2, 1000) <= 53);
+        assert!(bits_for_zoom(1e-15, 1000) > 53);
+        assert!(bits_for_zoom(1e-100, 1000) > 332 + 10);
+
+        // Deeper zooms should never need less precision:
+        let widths = (0..300).map(|e| 10f64.powi(-e));
+        let bits: Vec<u32> = widths.map(|w| bits_for_zoom(w, 1920)).collect();
+        assert!(bits.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn perturbation_at() {
+        // The precision shouldn't change the reference orbit (much) for
+        // shallow points:
+        for width in [1.0, 1e-20, 1e-60, 1e-200].iter() {
+            let mut perturbation = Perturbation::at("-0.75", "0.1", *width, 100).unwrap();
+            perturbation.reference.extend(20, 4.0);
+
+            let mut f64_reference = ReferenceOrbit::new(ComplexNumber::new(-0.75, 0.1));
+            f64_reference.extend(20, 4.0);
+
+            for (a, b) in perturbation
+                .reference
+                .orbit()
+                .iter()
+                .zip(f64_reference.orbit())
+            {
+                assert!((*a - *b).abs() < 1e-12);
+            }
+        }
+
+        assert!(Perturbation::at("-0.75", "zero", 1e-20, 100).is_err());
+    }
+
+    #[test]
+    fn series_approximation() {
+        let center = ComplexNumber::new(-0.743643887037151, 0.131825904205330);
+        let (radius, spacing) = (1e-9, 1e-11);
+
+        let mut reference = ReferenceOrbit::new(center);
+        reference.extend(1001, 4.0);
+
+        let series =
+            SeriesApproximation::new(reference.orbit(), 8, radius, spacing, 1e-3, 2.0, 1000);
+        let skipped = series.iterations();
+        assert!(skipped > 100 && skipped < 1000);
+
+        let iterate = |dc: ComplexNumber<f64>| {
+            reference.orbit()[..skipped]
+                .iter()
+                .fold(ComplexNumber::new(0.0, 0.0), |delta, z| {
+                    *z * delta * 2.0 + delta * delta + dc
+                })
+        };
+
+        // The series should land where iterating δ directly does, to within a
+        // thousandth of the distance between neighbouring pixels' orbits:
+        let separation = iterate(ComplexNumber::new(radius, 0.0)).abs() / radius * spacing;
+
+        for dc in [
+            ComplexNumber::new(radius, 0.0),
+            ComplexNumber::new(-0.3 * radius, 0.7 * radius),
+            ComplexNumber::new(0.0, -spacing),
+        ]
+        .iter()
+        {
+            assert!((series.evaluate(*dc) - iterate(*dc)).abs() < 1e-3 * separation);
+        }
+
+        // No terms (or nowhere to go) means nothing gets skipped:
+        let none = SeriesApproximation::new(reference.orbit(), 0, radius, spacing, 1e-3, 2.0, 1000);
+        assert_eq!(none.iterations(), 0);
+        let none = SeriesApproximation::new(reference.orbit(), 8, radius, spacing, 1e-3, 2.0, 0);
+        assert_eq!(none.iterations(), 0);
+    }
+
+    #[test]
+    fn reference_orbit_escapes() {
+        // 0 -> 1 -> 2 -> 5 -> ...
+        let mut reference = ReferenceOrbit::new(ComplexNumber::new(1.0, 0.0));
+        reference.extend(100, 4.0);
+
+        assert_eq!(reference.orbit().len(), 3);
+        assert_eq!(reference.orbit()[2], ComplexNumber::new(2.0, 0.0));
+    }
+}
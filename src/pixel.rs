@@ -1,13 +1,18 @@
 //! A (Generic) RGBA Pixel Type
 //!
-//! Lets you manipulate pixels in fun ways.
+//! Lets you manipulate pixels in fun ways, including converting them to and
+//! from HSV, HSL, CIE Lab/LCh and OKLab/OKLCh.
 
 extern crate num_traits;
 
 use self::num_traits::{AsPrimitive, Bounded, One, Unsigned, Zero};
+use error::{Error, Result};
 use std::{fmt::UpperHex, marker::Sized};
 
-#[derive(Clone)]
+/// `repr(C)` so that the channels are laid out in order (this is what lets
+/// `ImageBuffer` hand out `Pixel<u8>`s as RGBA bytes)
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Pixel<T: Unsigned + Bounded> {
     r: T,
     g: T,
@@ -17,7 +22,38 @@ pub struct Pixel<T: Unsigned + Bounded> {
 
 pub trait PixelMath<T: 'static + Unsigned + Bounded + Copy> {
     fn default() -> Self;
-    fn from_hsb(hue: f64, saturation: f64, brightness: f64) -> Result<Self, String>
+    fn from_hsb(hue: f64, saturation: f64, brightness: f64) -> Result<Self>
+    where
+        Self: Sized,
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>;
+
+    fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Result<Self>
+    where
+        Self: Sized,
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>;
+    fn from_linear_rgb(r: f64, g: f64, b: f64) -> Result<Self>
+    where
+        Self: Sized,
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>;
+    fn from_lab(lightness: f64, a: f64, b: f64) -> Result<Self>
+    where
+        Self: Sized,
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>;
+    fn from_lch(lightness: f64, chroma: f64, hue: f64) -> Result<Self>
+    where
+        Self: Sized,
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>;
+    fn from_oklab(lightness: f64, a: f64, b: f64) -> Result<Self>
+    where
+        Self: Sized,
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>;
+    fn from_oklch(lightness: f64, chroma: f64, hue: f64) -> Result<Self>
     where
         Self: Sized,
         f64: From<T> + AsPrimitive<T>,
@@ -39,7 +75,13 @@ pub trait PixelMath<T: 'static + Unsigned + Bounded + Copy> {
     fn get_slice(&self) -> [T; 4];
 
     fn to_hex(&self) -> String;
-    fn to_hsv(&self) -> (T, T, T);
+    fn to_hsv(&self) -> (f64, f64, f64);
+    fn to_hsl(&self) -> (f64, f64, f64);
+    fn to_linear_rgb(&self) -> (f64, f64, f64);
+    fn to_lab(&self) -> (f64, f64, f64);
+    fn to_lch(&self) -> (f64, f64, f64);
+    fn to_oklab(&self) -> (f64, f64, f64);
+    fn to_oklch(&self) -> (f64, f64, f64);
 }
 
 impl<T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>> PixelMath<T>
@@ -50,16 +92,19 @@ impl<T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>>
     }
 
     /// hue is in degrees, saturation and brightness are between 0 and 1
-    fn from_hsb(hue: f64, saturation: f64, brightness: f64) -> Result<Self, String>
+    fn from_hsb(hue: f64, saturation: f64, brightness: f64) -> Result<Self>
     where
         f64: From<T> + AsPrimitive<T>,
         T: Into<f64>,
     {
-        if saturation > 1f64 || brightness > 1f64 {
-            return Err(format!(
-                "Invalid HSB values: {} {} {}",
-                hue, saturation, brightness
-            ));
+        let in_range = |x: f64| (0f64..=1f64).contains(&x);
+
+        if !hue.is_finite() || !in_range(saturation) || !in_range(brightness) {
+            return Err(Error::InvalidColor {
+                hue,
+                saturation,
+                brightness,
+            });
         }
 
         let hh: f64;
@@ -73,7 +118,7 @@ impl<T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>>
         let (r, g, b) = if saturation <= 0f64 {
             (brightness, brightness, brightness)
         } else {
-            hh = (hue % 360f64) / 60f64;
+            hh = hue.rem_euclid(360f64) / 60f64;
             ii = hh as u64;
             ff = hh - hh.floor();
             pp = brightness * (1f64 - saturation);
@@ -91,15 +136,114 @@ impl<T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>>
             }
         };
 
-        let max = T::max_value().into();
+        Ok(Self::from_unit(r, g, b))
+    }
+
+    /// hue is in degrees, saturation and lightness are between 0 and 1
+    fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Result<Self>
+    where
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>,
+    {
+        let in_range = |x: f64| (0f64..=1f64).contains(&x);
+
+        if !hue.is_finite() || !in_range(saturation) || !in_range(lightness) {
+            return Err(Error::InvalidColorComponents(
+                "the hue must be finite and the saturation and lightness must be between 0 and 1",
+            ));
+        }
+
+        let (r, g, b) = hsl_to_rgb(hue, saturation, lightness);
+        Ok(Self::from_unit(r, g, b))
+    }
 
-        Ok(Self::new(
-            (r * max).round().as_(),
-            (g * max).round().as_(),
-            (b * max).round().as_(),
+    /// channels in linear light, between 0 and 1
+    fn from_linear_rgb(r: f64, g: f64, b: f64) -> Result<Self>
+    where
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>,
+    {
+        let in_range = |x: f64| (0f64..=1f64).contains(&x);
+
+        if !in_range(r) || !in_range(g) || !in_range(b) {
+            return Err(Error::InvalidColorComponents(
+                "linear channels must be between 0 and 1",
+            ));
+        }
+
+        Ok(Self::from_unit(
+            linear_to_srgb(r),
+            linear_to_srgb(g),
+            linear_to_srgb(b),
         ))
     }
 
+    /// CIE L*a*b* (D65 white); lightness is between 0 and 100 and colors
+    /// outside of sRGB are clamped
+    fn from_lab(lightness: f64, a: f64, b: f64) -> Result<Self>
+    where
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>,
+    {
+        if invalid_lab(lightness, 100f64, a, b) {
+            return Err(Error::InvalidColorComponents(
+                "Lab lightness must be between 0 and 100 and a and b must be finite",
+            ));
+        }
+
+        let (r, g, b) = lab_to_rgb(lightness, a, b);
+        Ok(Self::from_unit(r, g, b))
+    }
+
+    /// The polar form of `from_lab`; hue is in degrees
+    fn from_lch(lightness: f64, chroma: f64, hue: f64) -> Result<Self>
+    where
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>,
+    {
+        if invalid_polar(chroma, hue) {
+            return Err(Error::InvalidColorComponents(
+                "LCh chroma must be finite and not negative and the hue must be finite",
+            ));
+        }
+
+        let (a, b) = from_polar(chroma, hue);
+        Self::from_lab(lightness, a, b)
+    }
+
+    /// OKLab; lightness is between 0 and 1 and colors outside of sRGB are
+    /// clamped
+    fn from_oklab(lightness: f64, a: f64, b: f64) -> Result<Self>
+    where
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>,
+    {
+        if invalid_lab(lightness, 1f64, a, b) {
+            return Err(Error::InvalidColorComponents(
+                "OKLab lightness must be between 0 and 1 and a and b must be finite",
+            ));
+        }
+
+        let (r, g, b) = oklab_to_rgb(lightness, a, b);
+        Ok(Self::from_unit(r, g, b))
+    }
+
+    /// The polar form of `from_oklab`; hue is in degrees
+    fn from_oklch(lightness: f64, chroma: f64, hue: f64) -> Result<Self>
+    where
+        f64: From<T> + AsPrimitive<T>,
+        T: Into<f64>,
+    {
+        if invalid_polar(chroma, hue) {
+            return Err(Error::InvalidColorComponents(
+                "OKLCh chroma must be finite and not negative and the hue must be finite",
+            ));
+        }
+
+        let (a, b) = from_polar(chroma, hue);
+        Self::from_oklab(lightness, a, b)
+    }
+
     fn new(r: T, g: T, b: T) -> Self {
         Self::new_rgba(r, g, b, T::max_value())
     }
@@ -159,11 +303,269 @@ impl<T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>>
         format!("{:#X}{:X}{:X}{:X}", self.r, self.g, self.b, self.a)
     }
 
-    fn to_hsv(&self) -> (T, T, T) {
-        unimplemented!();
+    /// hue is in degrees, saturation and value are between 0 and 1 (the
+    /// inverse of `from_hsb`; HSB and HSV are the same thing); grays get a
+    /// hue of 0
+    fn to_hsv(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit();
+        rgb_to_hsv(r, g, b)
+    }
+
+    /// hue is in degrees, saturation and lightness are between 0 and 1
+    fn to_hsl(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit();
+        rgb_to_hsl(r, g, b)
+    }
+
+    fn to_linear_rgb(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit();
+        (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+    }
+
+    fn to_lab(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit();
+        rgb_to_lab(r, g, b)
+    }
+
+    fn to_lch(&self) -> (f64, f64, f64) {
+        let (l, a, b) = self.to_lab();
+        let (chroma, hue) = to_polar(a, b);
+        (l, chroma, hue)
+    }
+
+    fn to_oklab(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit();
+        rgb_to_oklab(r, g, b)
+    }
+
+    fn to_oklch(&self) -> (f64, f64, f64) {
+        let (l, a, b) = self.to_oklab();
+        let (chroma, hue) = to_polar(a, b);
+        (l, chroma, hue)
+    }
+}
+
+impl<T: 'static + Unsigned + Bounded + Copy + Into<f64>> Pixel<T> {
+    /// The color channels scaled to be between 0 and 1
+    fn to_unit(&self) -> (f64, f64, f64) {
+        let max = T::max_value().into();
+        (
+            self.r.into() / max,
+            self.g.into() / max,
+            self.b.into() / max,
+        )
+    }
+
+    /// An opaque pixel from channels between 0 and 1
+    fn from_unit(r: f64, g: f64, b: f64) -> Self
+    where
+        f64: AsPrimitive<T>,
+    {
+        let max = T::max_value().into();
+        // Wildly out of range Lab/OKLab colors can overflow into NaNs;
+        // those become 0:
+        let channel = |v: f64| {
+            if v.is_nan() {
+                T::zero()
+            } else {
+                (v.clamp(0.0, 1.0) * max).round().as_()
+            }
+        };
+
+        Pixel {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+            a: T::max_value(),
+        }
+    }
+}
+
+/// The sRGB transfer function, undone: an sRGB channel (between 0 and 1) in
+/// linear light
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The sRGB transfer function: a channel in linear light (between 0 and 1)
+/// in sRGB
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Hue (in degrees), saturation and value from sRGB channels
+pub(crate) fn rgb_to_hsv(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+    (hue(r, g, b, max, chroma), saturation, max)
+}
+
+pub(crate) fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> (f64, f64, f64) {
+    let chroma = value * saturation;
+    from_hue_chroma(hue, chroma, value - chroma)
+}
+
+/// Hue (in degrees), saturation and lightness from sRGB channels
+pub(crate) fn rgb_to_hsl(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let lightness = (max + min) / 2.0;
+
+    let saturation = if chroma == 0.0 {
+        0.0
+    } else {
+        // (rounding can push this just past 1 for very light colors)
+        (chroma / (1.0 - (2.0 * lightness - 1.0).abs())).min(1.0)
+    };
+    (hue(r, g, b, max, chroma), saturation, lightness)
+}
+
+pub(crate) fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (f64, f64, f64) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+}
+
+/// The hue (in degrees) HSV and HSL share
+fn hue(r: f64, g: f64, b: f64, max: f64, chroma: f64) -> f64 {
+    if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
     }
 }
 
+/// sRGB channels from a hue, a chroma and the amount `m` to add to each
+/// channel
+fn from_hue_chroma(hue: f64, chroma: f64, m: f64) -> (f64, f64, f64) {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    (r + m, g + m, b + m)
+}
+
+/// The D65 white point, in XYZ
+const WHITE: (f64, f64, f64) = (0.950_47, 1.0, 1.088_83);
+
+/// CIE L*a*b* from sRGB channels (by way of XYZ)
+pub(crate) fn rgb_to_lab(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+
+    let x = 0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b;
+    let z = 0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b;
+
+    let f = |t: f64| {
+        if t > 216.0 / 24_389.0 {
+            t.cbrt()
+        } else {
+            t * 841.0 / 108.0 + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / WHITE.0), f(y / WHITE.1), f(z / WHITE.2));
+
+    // (the matrix's rounding puts white a hair over 100)
+    let lightness = (116.0 * fy - 16.0).clamp(0.0, 100.0);
+    (lightness, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// Back to sRGB (clamped, since plenty of Lab colors are outside of sRGB)
+pub(crate) fn lab_to_rgb(lightness: f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let fy = (lightness + 16.0) / 116.0;
+    let (fx, fz) = (fy + a / 500.0, fy - b / 200.0);
+
+    let f = |t: f64| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            (t - 4.0 / 29.0) * 108.0 / 841.0
+        }
+    };
+    let (x, y, z) = (f(fx) * WHITE.0, f(fy) * WHITE.1, f(fz) * WHITE.2);
+
+    let channel = |v: f64| linear_to_srgb(v.clamp(0.0, 1.0));
+    (
+        channel(3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z),
+        channel(-0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z),
+        channel(0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z),
+    )
+}
+
+/// OKLab's lightness and a and b (from Björn Ottosson's reference code)
+pub(crate) fn rgb_to_oklab(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+
+    let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+    let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+    let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+
+    // (white can land a hair over 1 here too)
+    (
+        (0.210_454_255_3 * l + 0.793_617_785_0 * m - 0.004_072_046_8 * s).clamp(0.0, 1.0),
+        1.977_998_495_1 * l - 2.428_592_205_0 * m + 0.450_593_709_9 * s,
+        0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766_0 * s,
+    )
+}
+
+/// Back to sRGB (clamped, since blends can end up just outside of sRGB)
+pub(crate) fn oklab_to_rgb(lightness: f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let l = lightness + 0.396_337_777_4 * a + 0.215_803_757_3 * b;
+    let m = lightness - 0.105_561_345_8 * a - 0.063_854_172_8 * b;
+    let s = lightness - 0.089_484_177_5 * a - 1.291_485_548_0 * b;
+    let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+
+    let channel = |v: f64| linear_to_srgb(v.clamp(0.0, 1.0));
+    (
+        channel(4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s),
+        channel(-1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s),
+        channel(-0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701_0 * s),
+    )
+}
+
+/// Whether a lightness (up to `max`) and a and b, from Lab or OKLab, can't
+/// be made into a color
+fn invalid_lab(lightness: f64, max: f64, a: f64, b: f64) -> bool {
+    !(0f64..=max).contains(&lightness) || !a.is_finite() || !b.is_finite()
+}
+
+/// Whether a chroma and hue, from LCh or OKLCh, can't be made into a color
+fn invalid_polar(chroma: f64, hue: f64) -> bool {
+    !chroma.is_finite() || chroma < 0.0 || !hue.is_finite()
+}
+
+/// Chroma and hue (in degrees) from a and b
+fn to_polar(a: f64, b: f64) -> (f64, f64) {
+    (a.hypot(b), b.atan2(a).to_degrees().rem_euclid(360.0))
+}
+
+fn from_polar(chroma: f64, hue: f64) -> (f64, f64) {
+    let hue = hue.to_radians();
+    (chroma * hue.cos(), chroma * hue.sin())
+}
+
 pub struct IntoPixel<'a, T: 'a + Unsigned + Bounded> {
     px: &'a Pixel<T>,
     remaining: u8,
@@ -198,7 +600,10 @@ impl<'a, T: Unsigned + Bounded + Copy> Iterator for IntoPixel<'a, T> {
 
 #[cfg(test)]
 mod tests {
-    use pixel::{IntoPixel, Pixel, PixelMath};
+    use super::num_traits::{AsPrimitive, Bounded, Unsigned};
+    use error::{Error, Result};
+    use pixel::{linear_to_srgb, srgb_to_linear, IntoPixel, Pixel, PixelMath};
+    use std::fmt::{Debug, UpperHex};
 
     #[test]
     fn pixel_iterator() {
@@ -229,4 +634,205 @@ mod tests {
         test_hsb_to_rgb(0.0, 0.5, 0.0, 0, 0, 0);
         test_hsb_to_rgb(0.0, 0.0, 0.0, 0, 0, 0);
     }
+
+    #[test]
+    fn from_hsb_invalid() {
+        let invalid = |h: f64, s: f64, v: f64| {
+            assert_eq!(
+                Pixel::<u8>::from_hsb(h, s, v),
+                Err(Error::InvalidColor {
+                    hue: h,
+                    saturation: s,
+                    brightness: v
+                })
+            );
+        };
+
+        invalid(0.0, 1.5, 0.5);
+        invalid(0.0, 0.5, 1.01);
+        invalid(0.0, -0.1, 0.5);
+        invalid(0.0, 0.5, -1.0);
+        invalid(f64::INFINITY, 0.5, 0.5);
+
+        assert!(Pixel::<u8>::from_hsb(f64::NAN, 0.5, 0.5).is_err());
+        assert!(Pixel::<u8>::from_hsb(0.0, f64::NAN, 0.5).is_err());
+    }
+
+    fn close(actual: (f64, f64, f64), expected: (f64, f64, f64), tolerance: f64) {
+        assert!(
+            (actual.0 - expected.0).abs() <= tolerance
+                && (actual.1 - expected.1).abs() <= tolerance
+                && (actual.2 - expected.2).abs() <= tolerance,
+            "{:?} isn't {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn to_other_spaces() {
+        let red = Pixel::new(255u8, 0, 0);
+        let white = Pixel::new(255u8, 255, 255);
+
+        close(red.to_hsv(), (0.0, 1.0, 1.0), 1e-9);
+        close(
+            Pixel::new(64u8, 106, 128).to_hsv(),
+            (200.625, 0.5, 0.502),
+            1e-3,
+        );
+        close(
+            Pixel::new(128u8, 128, 128).to_hsv(),
+            (0.0, 0.0, 0.502),
+            1e-3,
+        );
+        close(Pixel::new(0u8, 0, 255).to_hsl(), (240.0, 1.0, 0.5), 1e-9);
+        close(white.to_hsl(), (0.0, 0.0, 1.0), 1e-9);
+
+        close(white.to_linear_rgb(), (1.0, 1.0, 1.0), 1e-9);
+        close(white.to_lab(), (100.0, 0.0, 0.0), 1e-3);
+        close(red.to_lab(), (53.24, 80.09, 67.20), 1e-2);
+        close(red.to_lch(), (53.24, 104.55, 40.0), 1e-2);
+        close(white.to_oklab(), (1.0, 0.0, 0.0), 1e-6);
+        close(red.to_oklab(), (0.627_96, 0.224_86, 0.125_85), 1e-4);
+        close(red.to_oklch(), (0.627_96, 0.257_68, 29.23), 1e-2);
+    }
+
+    #[test]
+    fn transfer_functions() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-12);
+        assert!((srgb_to_linear(0.5) - 0.214_04).abs() < 1e-5);
+
+        for i in 0..=1000 {
+            let v = i as f64 / 1000.0;
+            assert!((linear_to_srgb(srgb_to_linear(v)) - v).abs() < 1e-12);
+        }
+    }
+
+    /// Every channel of `px` survives the trip through `convert` to within
+    /// one code value
+    fn round_trips<T, F>(px: Pixel<T>, convert: F)
+    where
+        T: 'static + Unsigned + Bounded + Copy + Debug + UpperHex + Into<f64>,
+        F: Fn(&Pixel<T>) -> Pixel<T>,
+    {
+        let back = convert(&px);
+        let (a, b) = (px.get_tuple(), back.get_tuple());
+        let off = |x: T, y: T| (x.into() - y.into()).abs() > 1.0;
+
+        assert!(
+            !(off(a.0, b.0) || off(a.1, b.1) || off(a.2, b.2)),
+            "{:?} came back as {:?}",
+            px,
+            back
+        );
+    }
+
+    fn all_spaces<T>(px: Pixel<T>)
+    where
+        T: 'static + Unsigned + Bounded + Copy + Debug + UpperHex + Into<f64>,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        round_trips(px.clone(), |p| {
+            let (h, s, v) = p.to_hsv();
+            Pixel::from_hsb(h, s, v).unwrap()
+        });
+        round_trips(px.clone(), |p| {
+            let (h, s, l) = p.to_hsl();
+            Pixel::from_hsl(h, s, l).unwrap()
+        });
+        round_trips(px.clone(), |p| {
+            let (r, g, b) = p.to_linear_rgb();
+            Pixel::from_linear_rgb(r, g, b).unwrap()
+        });
+        round_trips(px.clone(), |p| {
+            let (l, a, b) = p.to_lab();
+            Pixel::from_lab(l, a, b).unwrap()
+        });
+        round_trips(px.clone(), |p| {
+            let (l, c, h) = p.to_lch();
+            Pixel::from_lch(l, c, h).unwrap()
+        });
+        round_trips(px.clone(), |p| {
+            let (l, a, b) = p.to_oklab();
+            Pixel::from_oklab(l, a, b).unwrap()
+        });
+        round_trips(px, |p| {
+            let (l, c, h) = p.to_oklch();
+            Pixel::from_oklch(l, c, h).unwrap()
+        });
+    }
+
+    #[test]
+    fn round_trip_grid() {
+        // Every 5th code value (which includes 0 and 255) on each channel:
+        for r in (0..=255u8).step_by(5) {
+            for g in (0..=255u8).step_by(5) {
+                for b in (0..=255u8).step_by(5) {
+                    all_spaces(Pixel::new(r, g, b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_random() {
+        // xorshift; deterministic so failures can be reproduced
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..20_000 {
+            let bits = next();
+            all_spaces(Pixel::new(
+                bits as u8,
+                (bits >> 8) as u8,
+                (bits >> 16) as u8,
+            ));
+            all_spaces(Pixel::new(
+                (bits >> 16) as u16,
+                (bits >> 32) as u16,
+                (bits >> 48) as u16,
+            ));
+        }
+    }
+
+    #[test]
+    fn other_spaces_invalid() {
+        let invalid = |result: Result<Pixel<u8>>| match result {
+            Err(Error::InvalidColorComponents(_)) => {}
+            other => panic!("expected an error, got {:?}", other),
+        };
+
+        invalid(Pixel::from_hsl(0.0, 1.5, 0.5));
+        invalid(Pixel::from_hsl(f64::NAN, 0.5, 0.5));
+        invalid(Pixel::from_linear_rgb(0.0, -0.5, 0.5));
+        invalid(Pixel::from_lab(101.0, 0.0, 0.0));
+        invalid(Pixel::from_lab(50.0, f64::INFINITY, 0.0));
+        invalid(Pixel::from_lch(50.0, -1.0, 0.0));
+        invalid(Pixel::from_oklab(1.5, 0.0, 0.0));
+        invalid(Pixel::from_oklch(0.5, 0.1, f64::NAN));
+
+        // Colors outside of sRGB are clamped (and ones so far out that the
+        // math overflows come out black):
+        assert_eq!(
+            Pixel::<u8>::from_oklab(0.5, 1e300, 1e300),
+            Ok(Pixel::new(0, 0, 0))
+        );
+        assert_eq!(
+            Pixel::<u8>::from_lab(50.0, 200.0, 0.0)
+                .unwrap()
+                .get_tuple()
+                .0,
+            255
+        );
+        assert_eq!(
+            Pixel::<u8>::from_hsb(-120.0, 1.0, 1.0),
+            Pixel::from_hsb(240.0, 1.0, 1.0)
+        );
+    }
 }
@@ -0,0 +1,347 @@
+//! Supersampling
+//!
+//! With one sample per pixel, the boundary of the set (which has detail at
+//! every scale) comes out as a mess of jagged, noisy pixels. `Supersampling`
+//! says where to put extra samples within each pixel; `Mandelbrot` colors
+//! each sample and then averages the colors (in linear light, with
+//! `average`) to get the color of the pixel.
+
+extern crate num_traits;
+
+use self::num_traits::{AsPrimitive, Bounded, One, Unsigned, Zero};
+use error::{Error, Result};
+use pixel::{linear_to_srgb, srgb_to_linear, Pixel, PixelMath};
+use std::{fmt::UpperHex, str::FromStr};
+
+/// Where to take samples within each pixel
+///
+/// Sample positions are given relative to the pixel's own point (the point a
+/// pixel gets without supersampling), in pixels, and cover the square from
+/// `-0.5` to `0.5` along both axes around it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Supersampling {
+    /// One sample per pixel, at the pixel's point
+    #[default]
+    None,
+    /// An `n` by `n` grid of samples
+    Grid(u32),
+    /// `n²` samples on a grid that's rotated (and wrapped around to stay in
+    /// the pixel) so that no two samples share a row or a column; this
+    /// handles nearly horizontal and vertical edges a lot better than a
+    /// regular grid with the same number of samples
+    ///
+    /// `RotatedGrid(2)` is the usual 4 sample RGSS pattern.
+    RotatedGrid(u32),
+    /// An `n` by `n` grid of samples, each moved to a random spot within its
+    /// grid cell
+    ///
+    /// Every pixel gets its own positions, but they only depend on the seed
+    /// and the pixel's position so renders are reproducible.
+    Jittered { size: u32, seed: u64 },
+}
+
+impl Supersampling {
+    /// The number of samples each pixel gets
+    pub fn samples(self) -> usize {
+        match self {
+            Supersampling::None => 1,
+            Supersampling::Grid(n)
+            | Supersampling::RotatedGrid(n)
+            | Supersampling::Jittered { size: n, .. } => (n as usize) * (n as usize),
+        }
+    }
+
+    /// Fails if the grid has no samples in it, or more than can be counted
+    pub fn validate(self) -> Result<()> {
+        let n = match self {
+            Supersampling::None => return Ok(()),
+            Supersampling::Grid(n)
+            | Supersampling::RotatedGrid(n)
+            | Supersampling::Jittered { size: n, .. } => n as usize,
+        };
+
+        match n.checked_mul(n) {
+            Some(0) => Err(Error::InvalidConfig(
+                "supersampling needs at least one sample per side",
+            )),
+            None => Err(Error::InvalidConfig("too many supersamples per pixel")),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Where sample number `sample` (from `0` to `samples() - 1`) of the
+    /// pixel at `pixel` goes, relative to the pixel's point (in pixels)
+    pub fn offset(self, pixel: (u32, u32), sample: usize) -> (f64, f64) {
+        match self {
+            Supersampling::None => (0.0, 0.0),
+            Supersampling::Grid(n) => {
+                let n = n as usize;
+                (cell(sample % n, n), cell(sample / n, n))
+            }
+            // Going across the rows of a grid `n²` cells wide (one row of
+            // `n` samples for each of the pixel's `n` columns) with the rows
+            // shifted by `n` cells each time:
+            Supersampling::RotatedGrid(n) => {
+                let n = n as usize;
+                let k = n * n;
+                let (i, j) = (sample % n, sample / n);
+                (cell(i * n + j, k), cell((n - 1 - j) * n + i, k))
+            }
+            Supersampling::Jittered { size, seed } => {
+                let n = size as usize;
+                let (x, y) = pixel;
+                let hash = mix(mix(mix(seed) ^ u64::from(x)) ^ u64::from(y)) ^ sample as u64;
+
+                (
+                    ((sample % n) as f64 + unit(mix(hash))) / n as f64 - 0.5,
+                    ((sample / n) as f64 + unit(mix(mix(hash)))) / n as f64 - 0.5,
+                )
+            }
+        }
+    }
+}
+
+/// The middle of cell `i` of `n` (across a pixel), relative to the middle of
+/// the pixel
+fn cell(i: usize, n: usize) -> f64 {
+    (i as f64 + 0.5) / n as f64 - 0.5
+}
+
+/// A step of SplitMix64, which is plenty random enough for sample positions
+fn mix(x: u64) -> u64 {
+    let x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Turns (the top 53 bits of) a random number into an `f64` in `[0, 1)`
+fn unit(x: u64) -> f64 {
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl FromStr for Supersampling {
+    type Err = Error;
+
+    /// Parses `none`, `grid:n`, `rotated:n`, `jittered:n` or
+    /// `jittered:n:seed` (the seed is 0 if it's left out)
+    fn from_str(s: &str) -> Result<Supersampling> {
+        let error = || Error::Parse {
+            input: s.to_string(),
+            expected: "supersampling (like `none`, `grid:3`, `rotated:2` or `jittered:4:7`)",
+        };
+
+        let parts: Vec<&str> = s.trim().split(':').map(str::trim).collect();
+        let number = |i: usize| parts.get(i).and_then(|p| p.parse::<u32>().ok());
+
+        let sampling = match (parts[0], parts.len()) {
+            ("none", 1) => Supersampling::None,
+            ("grid", 2) => Supersampling::Grid(number(1).ok_or_else(error)?),
+            ("rotated", 2) => Supersampling::RotatedGrid(number(1).ok_or_else(error)?),
+            ("jittered", 2) | ("jittered", 3) => Supersampling::Jittered {
+                size: number(1).ok_or_else(error)?,
+                seed: match parts.get(2) {
+                    Some(seed) => seed.parse().map_err(|_| error())?,
+                    None => 0,
+                },
+            },
+            _ => return Err(error()),
+        };
+
+        sampling.validate().map(|_| sampling).map_err(|_| error())
+    }
+}
+
+/// Averages colors in linear light: the sRGB transfer function is undone
+/// before the color channels are averaged and redone afterwards (alpha is
+/// averaged as is)
+///
+/// Averaging the sRGB values directly makes thin bright features (like the
+/// filaments of the set on a dark background) come out too dark. Gives back
+/// a black pixel if there are no colors to average.
+pub fn average<T>(colors: &[Pixel<T>]) -> Pixel<T>
+where
+    T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>,
+    f64: AsPrimitive<T>,
+{
+    if colors.is_empty() {
+        return Pixel::new(T::zero(), T::zero(), T::zero());
+    }
+
+    let max: f64 = T::max_value().into();
+    let count = colors.len() as f64;
+    let mut sums = [0f64; 4];
+
+    for color in colors {
+        for (i, (sum, value)) in sums.iter_mut().zip(color.get_slice().iter()).enumerate() {
+            let value = (*value).into() / max;
+            *sum += if i < 3 { srgb_to_linear(value) } else { value };
+        }
+    }
+
+    let channel = |sum: f64, linear: bool| -> T {
+        let value = sum / count;
+        let value = if linear { linear_to_srgb(value) } else { value };
+        (value.clamp(0.0, 1.0) * max).round().as_()
+    };
+
+    Pixel::new_rgba(
+        channel(sums[0], true),
+        channel(sums[1], true),
+        channel(sums[2], true),
+        channel(sums[3], false),
+    )
+}
+
+/// How much two colors differ: the biggest difference between any of their
+/// color channels, as a fraction of the range (for adaptive supersampling)
+pub(crate) fn difference<T>(a: &Pixel<T>, b: &Pixel<T>) -> f64
+where
+    T: 'static + Unsigned + Bounded + UpperHex + Zero + One + Copy + Into<f64>,
+{
+    let max: f64 = T::max_value().into();
+    let (a, b) = (a.get_slice(), b.get_slice());
+
+    a[..3]
+        .iter()
+        .zip(b[..3].iter())
+        .map(|(a, b)| ((*a).into() - (*b).into()).abs() / max)
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use pixel::{Pixel, PixelMath};
+    use sampling::{average, difference, Supersampling};
+
+    fn offsets(sampling: Supersampling, pixel: (u32, u32)) -> Vec<(f64, f64)> {
+        (0..sampling.samples())
+            .map(|s| sampling.offset(pixel, s))
+            .collect()
+    }
+
+    #[test]
+    fn patterns() {
+        assert_eq!(offsets(Supersampling::None, (3, 4)), vec![(0.0, 0.0)]);
+        assert_eq!(
+            offsets(Supersampling::Grid(2), (0, 0)),
+            vec![(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]
+        );
+        assert_eq!(
+            offsets(Supersampling::RotatedGrid(2), (0, 0)),
+            vec![
+                (-0.375, 0.125),
+                (0.125, 0.375),
+                (-0.125, -0.375),
+                (0.375, -0.125)
+            ]
+        );
+
+        // Every pattern stays in the pixel, and rotated grids never put two
+        // samples in the same row or column:
+        for n in 1..6 {
+            let rotated = offsets(Supersampling::RotatedGrid(n), (0, 0));
+            let jittered = offsets(Supersampling::Jittered { size: n, seed: 1 }, (5, 9));
+
+            for &(x, y) in offsets(Supersampling::Grid(n), (0, 0))
+                .iter()
+                .chain(rotated.iter())
+                .chain(jittered.iter())
+            {
+                assert!((-0.5..0.5).contains(&x) && (-0.5..0.5).contains(&y));
+            }
+
+            for (i, a) in rotated.iter().enumerate() {
+                for b in rotated[i + 1..].iter() {
+                    assert!(a.0 != b.0 && a.1 != b.1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn big_grids() {
+        // `n²` doesn't fit in a `u32` here:
+        let rotated = Supersampling::RotatedGrid(70_000);
+        assert!(rotated.validate().is_ok());
+
+        let (x, y) = rotated.offset((0, 0), rotated.samples() - 1);
+        assert!((-0.5..0.5).contains(&x) && (-0.5..0.5).contains(&y));
+
+        assert!(Supersampling::Grid(0).validate().is_err());
+    }
+
+    #[test]
+    fn jitter() {
+        let jittered = |seed, pixel| offsets(Supersampling::Jittered { size: 3, seed }, pixel);
+
+        // The same seed and pixel always give the same positions; anything
+        // else gives different ones:
+        assert_eq!(jittered(7, (1, 2)), jittered(7, (1, 2)));
+        assert_ne!(jittered(7, (1, 2)), jittered(8, (1, 2)));
+        assert_ne!(jittered(7, (1, 2)), jittered(7, (2, 1)));
+
+        // Each sample stays in its own cell of the grid:
+        for (s, &(x, y)) in jittered(7, (1, 2)).iter().enumerate() {
+            let (i, j) = ((s % 3) as f64, (s / 3) as f64);
+            assert!(i / 3.0 - 0.5 <= x && x < (i + 1.0) / 3.0 - 0.5);
+            assert!(j / 3.0 - 0.5 <= y && y < (j + 1.0) / 3.0 - 0.5);
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        let parse = |s: &str| s.parse::<Supersampling>();
+
+        assert_eq!(parse("none"), Ok(Supersampling::None));
+        assert_eq!(parse("grid:3"), Ok(Supersampling::Grid(3)));
+        assert_eq!(parse(" rotated : 2 "), Ok(Supersampling::RotatedGrid(2)));
+        assert_eq!(
+            parse("jittered:4"),
+            Ok(Supersampling::Jittered { size: 4, seed: 0 })
+        );
+        assert_eq!(
+            parse("jittered:4:99"),
+            Ok(Supersampling::Jittered { size: 4, seed: 99 })
+        );
+
+        for bad in [
+            "",
+            "grid",
+            "grid:0",
+            "grid:-1",
+            "rotated:2:3",
+            "jittered:2:x",
+            "box:2",
+        ]
+        .iter()
+        {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn averaging() {
+        let (black, white) = (Pixel::new(0u8, 0, 0), Pixel::new(255u8, 255, 255));
+
+        // Half way between black and white in linear light is a lot lighter
+        // than 128:
+        assert_eq!(
+            average(&[black.clone(), white.clone()]),
+            Pixel::new(188, 188, 188)
+        );
+        assert_eq!(average(&vec![white.clone(); 5]), white);
+        assert_eq!(
+            average(&[
+                Pixel::new_rgba(10u16, 20, 30, 0),
+                Pixel::new_rgba(10, 20, 30, 100)
+            ]),
+            Pixel::new_rgba(10, 20, 30, 50)
+        );
+        assert_eq!(average::<u8>(&[]), black);
+
+        assert_eq!(difference(&black, &white), 1.0);
+        assert_eq!(difference(&Pixel::new(0u8, 51, 0), &black), 0.2);
+    }
+}
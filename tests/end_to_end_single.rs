@@ -5,17 +5,10 @@ use mandelbrot::{
     color_scale::{ColorScale, ContinuousColorScale, DiscreteColorScale, SimpleColorScale},
     complex_number::ComplexNumber,
     mandelbrot::{Mandelbrot, MandelbrotConfig, Viewport},
-    pixel::{IntoPixel, Pixel},
 };
 use png::Decoder;
 use std::{fs::File, path::Path};
 
-fn flatten_array(grid: &Vec<Vec<Pixel<u8>>>) -> Vec<u8> {
-    grid.iter()
-        .flat_map(|col| col.iter().flat_map(|pixel| IntoPixel::<u8>::new(pixel)))
-        .collect()
-}
-
 fn vec_compare(uno: &[u8], dos: &[u8]) -> bool {
     (uno.len() == dos.len())
         && uno.iter().zip(dos).enumerate().all(|(i, (a, b))| {
@@ -32,7 +25,7 @@ fn single_frame_test_runner(
     viewport: (ComplexNumber<f64>, f64, f64),
     dimensions: (u32, u32),
     iters: u32,
-    color_fn: Box<Fn(u32, ComplexNumber<f64>, u32) -> Pixel<u8>>,
+    color_fn: Box<dyn ColorScale<u8>>,
 ) {
     let file = File::open(Path::new(path)).unwrap();
     let decoder = Decoder::new(file);
@@ -42,27 +35,18 @@ fn single_frame_test_runner(
     reader.next_frame(&mut buf).unwrap();
 
     let (top_left, width, height) = viewport;
-    let viewport = Viewport::<f64> {
-        top_left,
-        width,
-        height,
-    };
-    let config = MandelbrotConfig::<u8> {
-        dimensions,
-        viewport,
-        color_fn,
-    };
+    let viewport = Viewport::new(top_left, width, height);
+    let config = MandelbrotConfig::<u8>::new(dimensions, viewport, color_fn);
 
-    let mut mandelbrot = Mandelbrot::new(config);
+    let mut mandelbrot = Mandelbrot::new(config).unwrap();
 
     mandelbrot.run_iterations(iters);
-    let data = mandelbrot.get_pixels();
-    let data = flatten_array(data);
+    let data = mandelbrot.get_pixels().as_rgba_bytes();
 
     assert_eq!(buf.len(), data.len());
     println!("{:?}", buf.len());
 
-    assert!(vec_compare(&buf, &data));
+    assert!(vec_compare(&buf, data));
 }
 
 #[test]
@@ -76,7 +60,7 @@ fn single_frame_simple_color() {
         ),
         (1920, 1080),
         50,
-        Box::new(SimpleColorScale::pixel_color),
+        Box::new(SimpleColorScale {}),
     );
 }
 
@@ -87,7 +71,7 @@ fn single_frame_discrete_colors() {
         (ComplexNumber::new(-3.0, 1.15), 4.0, (1080.0 / 1920.0) * 4.0),
         (1920, 1080),
         50,
-        Box::new(DiscreteColorScale::pixel_color),
+        Box::new(DiscreteColorScale::default()),
     );
 }
 
@@ -98,6 +82,6 @@ fn single_frame_continuous_colors() {
         (ComplexNumber::new(-3.0, 1.15), 4.0, (1080.0 / 1920.0) * 4.0),
         (1920, 1080),
         50,
-        ContinuousColorScale::get_color_fn_boxed(140.0, 1.0, 1.0),
+        Box::new(ContinuousColorScale::new(140.0, 1.0, 1.0, 10.0).unwrap()),
     );
 }